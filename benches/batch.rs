@@ -0,0 +1,34 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    hint::black_box,
+};
+use tcmalloc_better::TCMalloc;
+
+fn batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch");
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    for count in [64, 256] {
+        let mut ptrs = vec![std::ptr::null_mut(); count];
+        group.bench_function(BenchmarkId::new("alloc_batch", count), |b| {
+            b.iter(|| unsafe {
+                assert_eq!(TCMalloc::alloc_batch(layout, &mut ptrs), count);
+                TCMalloc::free_batch(black_box(&ptrs), layout);
+            })
+        });
+        group.bench_function(BenchmarkId::new("alloc", count), |b| {
+            b.iter(|| unsafe {
+                for ptr in ptrs.iter_mut() {
+                    *ptr = TCMalloc.alloc(layout);
+                }
+                for &ptr in black_box(&ptrs).iter() {
+                    TCMalloc.dealloc(ptr, layout);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, batch);
+criterion_main!(benches);
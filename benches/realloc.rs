@@ -0,0 +1,51 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    hint::black_box,
+};
+use tcmalloc_better::TCMalloc;
+
+/// Default [`GlobalAlloc::realloc`], which always moves the allocation.
+unsafe fn realloc_by_copy(ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    unsafe {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_ptr = TCMalloc.alloc(new_layout);
+        if !new_ptr.is_null() {
+            core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            TCMalloc.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+fn realloc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("realloc");
+    for (name, size, new_size) in [
+        ("shrink_same_class", 1000, 900),
+        ("grow_same_class", 900, 1000),
+        ("grow_twice", 1024, 2048),
+        ("shrink_same_pages", (1 << 20) + 2000, (1 << 20) + 100),
+        ("grow_twice_large", 1 << 20, 2 << 20),
+    ] {
+        let layout = Layout::from_size_align(size, 16).unwrap();
+        let new_layout = Layout::from_size_align(new_size, 16).unwrap();
+        group.bench_function(BenchmarkId::new("global_alloc", name), |b| {
+            b.iter(|| unsafe {
+                let ptr = TCMalloc.alloc(layout);
+                let ptr = TCMalloc.realloc(ptr, layout, black_box(new_size));
+                TCMalloc.dealloc(black_box(ptr), new_layout);
+            })
+        });
+        group.bench_function(BenchmarkId::new("copy", name), |b| {
+            b.iter(|| unsafe {
+                let ptr = TCMalloc.alloc(layout);
+                let ptr = realloc_by_copy(ptr, layout, black_box(new_size));
+                TCMalloc.dealloc(black_box(ptr), new_layout);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, realloc);
+criterion_main!(benches);
@@ -1,5 +1,9 @@
 #include "tcmalloc/tcmalloc.cc"
 
+using tcmalloc::tcmalloc_internal::kMaxObjectsToMove;
+using tcmalloc::tcmalloc_internal::kSelSanPresent;
+using tcmalloc::tcmalloc_internal::NumCPUsMaybe;
+
 extern "C" {
     ABSL_ATTRIBUTE_UNUSED ABSL_CACHELINE_ALIGNED void* BridgeTCMallocInternalNewAlignedNothrow(
         size_t size, std::align_val_t alignment
@@ -10,7 +14,7 @@ extern "C" {
     // This code is based on `do_realloc` with alignment acceptance and without copying.
     // Rust code should make copying with the knowledge of properly alignment.
     //
-    // TODO: migrate whole realloc logic to rust
+    // Deprecated: `tcmalloc-better` resizes in place in Rust with the bridges below.
     ABSL_ATTRIBUTE_UNUSED ABSL_CACHELINE_ALIGNED void* BridgePrepareReallocAligned(
         void* old_ptr, size_t new_size, std::align_val_t alignment, size_t* old_size_p
     ) {
@@ -83,4 +87,154 @@ extern "C" {
 
       return new_ptr;
     }
+
+    // Returns the size class of the allocation at `ptr` or 0 if it is allocated by pages.
+    ABSL_ATTRIBUTE_UNUSED size_t BridgeGetSizeClassOf(const void* ptr) {
+        return tc_globals.pagemap().sizeclass(PageIdContainingTagged(ptr));
+    }
+
+    // Returns the size class which `free` with the `size` and `alignment` would use for the
+    // allocation at `ptr`, or 0 if such allocation is freed by pages.
+    ABSL_ATTRIBUTE_UNUSED size_t BridgeGetSizeClassFor(
+        void* ptr, size_t size, std::align_val_t alignment
+    ) {
+        TC_ASSERT(absl::has_single_bit(static_cast<size_t>(alignment)));
+        size_t size_class;
+        if (!tc_globals.sizemap().GetSizeClass(
+                CppPolicy().AlignAs(alignment).InSameNumaPartitionAs(ptr), size, &size_class)) {
+            return 0;
+        }
+        return size_class;
+    }
+
+    // Returns the size of the allocation at `ptr`, which is allocated by pages, or 0 if it can
+    // not be resized in place (it is sampled or owned by the guarded page allocator).
+    ABSL_ATTRIBUTE_UNUSED size_t BridgeGetResizablePagesSize(const void* ptr) {
+        Span* span = tc_globals.pagemap().GetExistingDescriptor(PageIdContainingTagged(ptr));
+        if (ABSL_PREDICT_FALSE(span == nullptr)) {
+            ReportDoubleFree(tc_globals, const_cast<void*>(ptr));
+        }
+        if (span->sampled() || tc_globals.guardedpage_allocator().PointerIsMine(ptr)) {
+            return 0;
+        }
+        return span->bytes_in_span();
+    }
+
+    // Returns `size` rounded up to the whole number of pages.
+    ABSL_ATTRIBUTE_UNUSED size_t BridgeRoundUpToPages(size_t size) {
+        return BytesToLengthCeil(size).in_bytes();
+    }
+
+    // Completes resizing of the allocation at `ptr` to `new_size` in place, after the caller has
+    // checked that the size class or the number of pages stays the same. Like `do_realloc`,
+    // reports the growth to the sampler and invokes the hooks with the updated size. Returns
+    // false without any changes if the growth would be sampled, so the allocation must be moved.
+    ABSL_ATTRIBUTE_UNUSED bool BridgeCompleteResizeInPlace(void* ptr, size_t new_size) {
+        const PageId p = PageIdContainingTagged(ptr);
+        const size_t size_class = tc_globals.pagemap().sizeclass(p);
+        size_t old_size;
+        size_t actual_new_size;
+        if (size_class != 0) {
+            old_size = tc_globals.sizemap().class_to_size(size_class);
+            actual_new_size = old_size;
+        } else {
+            old_size = GetLargeSize(ptr, p);
+            actual_new_size = BytesToLengthCeil(new_size).in_bytes();
+        }
+        if (new_size > old_size) {
+            if (GetThreadSampler()->WillRecordAllocation(new_size - old_size)) {
+                return false;
+            }
+            GetThreadSampler()->ReportAllocation(new_size - old_size);
+        }
+        tcmalloc::MallocHook::InvokeDeleteHook(
+            {ptr, old_size, tcmalloc::HookMemoryMutable::kImmutable});
+        tcmalloc::MallocHook::InvokeNewHook(
+            {ptr, new_size, actual_new_size, tcmalloc::HookMemoryMutable::kImmutable});
+        TC_ASSERT(GetSize(ptr) == actual_new_size);
+        return true;
+    }
+
+    // Bounds-checked `MallocExtension_Internal_ReleaseCpuMemory`, which indexes per-CPU caches
+    // by `cpu` unchecked. Returns 0 if `cpu` is not in `[0, NumCPUs())`.
+    ABSL_ATTRIBUTE_UNUSED size_t BridgeReleaseCpuMemory(int cpu) {
+        const std::optional<int> num_cpus = NumCPUsMaybe();
+        if (cpu < 0 || !num_cpus.has_value() || cpu >= *num_cpus) {
+            return 0;
+        }
+        return MallocExtension_Internal_ReleaseCpuMemory(cpu);
+    }
+
+    // Allocates up to `count` objects of `size` and `alignment` into `batch` and returns the
+    // number of allocated objects, which is less than `count` only when out of memory.
+    // Objects of small size classes are taken from the transfer cache by up to
+    // `kMaxObjectsToMove` at once, bypassing per-CPU caches, sampling and hooks.
+    ABSL_ATTRIBUTE_UNUSED size_t BridgeAllocBatch(
+        size_t size, std::align_val_t alignment, void** batch, size_t count
+    ) {
+        TC_ASSERT(absl::has_single_bit(static_cast<size_t>(alignment)));
+        tc_globals.InitIfNecessary();
+        const auto policy = CppPolicy().Nothrow().AlignAs(alignment);
+        size_t size_class;
+        if (kSelSanPresent || tc_globals.HaveHooks() ||
+            !tc_globals.sizemap().GetSizeClass(policy, size, &size_class)) {
+            for (size_t i = 0; i < count; ++i) {
+                batch[i] = fast_alloc(size, policy);
+                if (ABSL_PREDICT_FALSE(batch[i] == nullptr)) {
+                    return i;
+                }
+            }
+            return count;
+        }
+        size_t total = 0;
+        while (total < count) {
+            const size_t want = std::min(kMaxObjectsToMove, count - total);
+            const int got = tc_globals.transfer_cache().RemoveRange(
+                size_class, absl::MakeSpan(batch + total, want));
+            if (ABSL_PREDICT_FALSE(got == 0)) {
+                break;
+            }
+            total += got;
+        }
+        return total;
+    }
+
+    // Frees `count` objects of `size` and `alignment` from `batch`. Consecutive objects of the
+    // same size class are returned to the transfer cache by up to `kMaxObjectsToMove` at once,
+    // objects allocated by pages (e.g. sampled ones) are freed one by one.
+    ABSL_ATTRIBUTE_UNUSED void BridgeFreeBatch(
+        void* const* batch, size_t count, size_t size, std::align_val_t alignment
+    ) {
+        TC_ASSERT(absl::has_single_bit(static_cast<size_t>(alignment)));
+        if (kSelSanPresent || tc_globals.HaveHooks()) {
+            for (size_t i = 0; i < count; ++i) {
+                TCMallocInternalDeleteSizedAligned(batch[i], size, alignment);
+            }
+            return;
+        }
+        void* objects[kMaxObjectsToMove];
+        size_t objects_size_class = 0;
+        size_t num_objects = 0;
+        for (size_t i = 0; i < count; ++i) {
+            const size_t size_class =
+                tc_globals.pagemap().sizeclass(PageIdContainingTagged(batch[i]));
+            if (ABSL_PREDICT_FALSE(size_class == 0)) {
+                TCMallocInternalDeleteSizedAligned(batch[i], size, alignment);
+                continue;
+            }
+            if (num_objects == kMaxObjectsToMove || size_class != objects_size_class) {
+                if (num_objects != 0) {
+                    tc_globals.transfer_cache().InsertRange(
+                        objects_size_class, absl::MakeSpan(objects, num_objects));
+                }
+                objects_size_class = size_class;
+                num_objects = 0;
+            }
+            objects[num_objects++] = batch[i];
+        }
+        if (num_objects != 0) {
+            tc_globals.transfer_cache().InsertRange(
+                objects_size_class, absl::MakeSpan(objects, num_objects));
+        }
+    }
 }
@@ -1,11 +1,122 @@
+#include <algorithm>
+#include <cstdint>
+#include <cstring>
+#include <map>
+#include <optional>
+#include <string>
+
+#include "absl/numeric/bits.h"
+#include "absl/strings/string_view.h"
+#include "absl/time/time.h"
 #include "tcmalloc/malloc_extension.h"
 
+// `tcmalloc/internal_malloc_extension.h` declares it weak, so it is not linked from the static
+// library unless referenced strongly, and `NeedsProcessBackgroundActions` would return false.
+extern "C" void MallocExtension_Internal_ProcessBackgroundActions();
+
 extern "C" {
     bool NeedsProcessBackgroundActions() {
         return tcmalloc::MallocExtension::NeedsProcessBackgroundActions();
     }
 
     void ProcessBackgroundActions() {
-        tcmalloc::MallocExtension::ProcessBackgroundActions();
+        MallocExtension_Internal_ProcessBackgroundActions();
+    }
+
+    bool BridgeGetBackgroundProcessActionsEnabled() {
+        return tcmalloc::MallocExtension::GetBackgroundProcessActionsEnabled();
+    }
+
+    void BridgeSetBackgroundProcessActionsEnabled(bool value) {
+        tcmalloc::MallocExtension::SetBackgroundProcessActionsEnabled(value);
+    }
+
+    int64_t BridgeGetBackgroundProcessSleepInterval() {
+        return absl::ToInt64Nanoseconds(
+            tcmalloc::MallocExtension::GetBackgroundProcessSleepInterval());
+    }
+
+    void BridgeSetBackgroundProcessSleepInterval(int64_t nanoseconds) {
+        tcmalloc::MallocExtension::SetBackgroundProcessSleepInterval(
+            absl::Nanoseconds(nanoseconds));
+    }
+
+    size_t BridgeGetBackgroundReleaseRate() {
+        return static_cast<size_t>(tcmalloc::MallocExtension::GetBackgroundReleaseRate());
+    }
+
+    void BridgeSetBackgroundReleaseRate(size_t bytes_per_second) {
+        tcmalloc::MallocExtension::SetBackgroundReleaseRate(
+            static_cast<tcmalloc::MallocExtension::BytesPerSecond>(bytes_per_second));
+    }
+
+    // The report is built in a `std::string`, which is served by the system allocator
+    // (tcmalloc is compiled with `TCMALLOC_INTERNAL_METHODS_ONLY`), so it does not recurse
+    // into the Rust global allocator.
+    size_t BridgeGetStats(char* buffer, size_t buffer_length) {
+        const std::string stats = tcmalloc::MallocExtension::GetStats();
+        if (buffer_length > 0) {
+            std::memcpy(buffer, stats.data(), std::min(stats.size(), buffer_length));
+        }
+        return stats.size();
+    }
+
+    bool BridgeGetNumericProperty(const char* name_data, size_t name_size, size_t* value) {
+        const std::optional<size_t> property =
+            tcmalloc::MallocExtension::GetNumericProperty(absl::string_view(name_data, name_size));
+        if (!property.has_value()) {
+            return false;
+        }
+        *value = *property;
+        return true;
+    }
+
+    // The map is built by the system allocator, `callback` is invoked only after it is complete,
+    // so it is safe to allocate from within `callback`.
+    void BridgeGetProperties(
+        void (*callback)(void* context, const char* name_data, size_t name_size, size_t value),
+        void* context
+    ) {
+        const std::map<std::string, tcmalloc::MallocExtension::Property> properties =
+            tcmalloc::MallocExtension::GetProperties();
+        for (const auto& [name, property] : properties) {
+            callback(context, name.data(), name.size(), property.value);
+        }
+    }
+
+    int BridgeGetOwnership(const void* ptr) {
+        return static_cast<int>(tcmalloc::MallocExtension::GetOwnership(ptr));
+    }
+
+    // `GetAllocatedSize` requires `ptr` to be owned by tcmalloc and fails hard otherwise,
+    // so ownership is checked first.
+    bool BridgeGetAllocatedSize(const void* ptr, size_t* size) {
+        if (tcmalloc::MallocExtension::GetOwnership(ptr) !=
+            tcmalloc::MallocExtension::Ownership::kOwned) {
+            return false;
+        }
+        const std::optional<size_t> allocated_size =
+            tcmalloc::MallocExtension::GetAllocatedSize(ptr);
+        if (!allocated_size.has_value()) {
+            return false;
+        }
+        *size = *allocated_size;
+        return true;
+    }
+
+    // `nallocx` is the alignment-aware form of `GetEstimatedAllocatedSize`, it performs the same
+    // size class lookup as the aligned allocation path.
+    size_t BridgeGetEstimatedAllocatedSize(size_t size, size_t alignment) {
+        return nallocx(size, MALLOCX_LG_ALIGN(absl::countr_zero(alignment)));
+    }
+
+    size_t BridgeGetMemoryLimit(int limit_kind) {
+        return tcmalloc::MallocExtension::GetMemoryLimit(
+            static_cast<tcmalloc::MallocExtension::LimitKind>(limit_kind));
+    }
+
+    void BridgeSetMemoryLimit(size_t limit, int limit_kind) {
+        tcmalloc::MallocExtension::SetMemoryLimit(
+            limit, static_cast<tcmalloc::MallocExtension::LimitKind>(limit_kind));
     }
 }
@@ -4,9 +4,117 @@ unsafe extern "C" {
 
     /// Runs housekeeping actions for the allocator off of the main allocation path.
     ///
-    /// Should be run in the background thread. May return or may not return.
+    /// Should be run in the background thread. Returns when background actions are disabled
+    /// by `BridgeSetBackgroundProcessActionsEnabled`.
     pub fn ProcessBackgroundActions();
 
+    /// Return true if background actions are enabled.
+    pub fn BridgeGetBackgroundProcessActionsEnabled() -> bool;
+
+    /// Enable or disable background actions. When disabled, `ProcessBackgroundActions` returns
+    /// after the current sleep interval.
+    pub fn BridgeSetBackgroundProcessActionsEnabled(value: bool);
+
+    /// Get the interval between background actions in nanoseconds.
+    pub fn BridgeGetBackgroundProcessSleepInterval() -> i64;
+
+    /// Set the interval between background actions in nanoseconds.
+    pub fn BridgeSetBackgroundProcessSleepInterval(nanoseconds: i64);
+
+    /// Get the rate in bytes per second at which background actions release memory from the page
+    /// heap. Zero disables the release.
+    pub fn BridgeGetBackgroundReleaseRate() -> libc::size_t;
+
+    /// Set the rate in bytes per second at which background actions release memory from the page
+    /// heap. Zero disables the release.
+    pub fn BridgeSetBackgroundReleaseRate(bytes_per_second: libc::size_t);
+
     /// Sets the maximum cache size per CPU cache. This is a per-core limit.
     pub fn TCMalloc_Internal_SetMaxPerCpuCacheSize(value: i32);
+
+    /// Tries to release at least `bytes` of free memory back to the OS.
+    ///
+    /// Returns the number of released bytes, at most `bytes`. Memory released in excess of
+    /// `bytes` is accounted towards the following calls.
+    pub fn MallocExtension_Internal_ReleaseMemoryToSystem(bytes: libc::size_t) -> libc::size_t;
+
+    /// Marks the current thread as idle, releasing its thread-specific resources. It may be
+    /// expensive, so it should not be called too often.
+    pub fn MallocExtension_Internal_MarkThreadIdle();
+
+    /// Marks the current thread as busy after `MallocExtension_Internal_MarkThreadIdle`.
+    pub fn MallocExtension_Internal_MarkThreadBusy();
+
+    /// Frees the resources cached for `cpu`, may be called from any CPU.
+    ///
+    /// Returns the number of freed bytes previously cached for `cpu`. `cpu` must be in
+    /// `[0, number of CPUs)`, it is not bounds-checked, see `BridgeReleaseCpuMemory`.
+    pub fn MallocExtension_Internal_ReleaseCpuMemory(cpu: libc::c_int) -> libc::size_t;
+
+    /// Copy a human-readable description of the current state of the malloc data structures
+    /// into `buffer`.
+    ///
+    /// At most `buffer_length` bytes are written. The copied text is not NUL-terminated.
+    ///
+    /// Returns the full length of the description, which may be greater than `buffer_length`.
+    /// In that case the copied text is truncated and the caller should retry with a larger buffer.
+    pub fn BridgeGetStats(buffer: *mut libc::c_char, buffer_length: libc::size_t) -> libc::size_t;
+
+    /// Get the named property's value.
+    ///
+    /// `name_data` must point to `name_size` bytes of the property name, it does not need to be
+    /// NUL-terminated.
+    ///
+    /// Returns false if the property is not valid. In that case `value` is left untouched.
+    pub fn BridgeGetNumericProperty(
+        name_data: *const libc::c_char,
+        name_size: libc::size_t,
+        value: *mut libc::size_t,
+    ) -> bool;
+
+    /// Get detailed statistics about the state of TCMalloc.
+    ///
+    /// `callback` is invoked once per statistic in the name order with the passed `context`,
+    /// the statistic name (`name_data` points to `name_size` bytes, not NUL-terminated) and its value.
+    /// The name pointer is valid only during the `callback` call.
+    pub fn BridgeGetProperties(
+        callback: unsafe extern "C" fn(
+            context: *mut core::ffi::c_void,
+            name_data: *const libc::c_char,
+            name_size: libc::size_t,
+            value: libc::size_t,
+        ),
+        context: *mut core::ffi::c_void,
+    );
+
+    /// Returns ownership of the memory pointed to by `ptr`:
+    /// 0 if unknown, 1 if allocated by TCMalloc, 2 if allocated elsewhere or `ptr` is null.
+    pub fn BridgeGetOwnership(ptr: *const core::ffi::c_void) -> libc::c_int;
+
+    /// Get the number of bytes usable by the allocation at `ptr`.
+    ///
+    /// `ptr` must be the pointer returned by the allocation and must not be freed yet.
+    ///
+    /// Returns false if `ptr` is not owned by TCMalloc. In that case `size` is left untouched.
+    pub fn BridgeGetAllocatedSize(ptr: *const core::ffi::c_void, size: *mut libc::size_t) -> bool;
+
+    /// Returns the number of bytes which will be allocated for a request of `size` bytes
+    /// with the `alignment`. The allocation may reserve more bytes, but will never reserve fewer.
+    ///
+    /// `alignment` must be a power of two.
+    pub fn BridgeGetEstimatedAllocatedSize(
+        size: libc::size_t,
+        alignment: libc::size_t,
+    ) -> libc::size_t;
+
+    /// Get the memory limit of `limit_kind`: 0 for the soft limit, 1 for the hard limit.
+    ///
+    /// Returns `size_t::MAX` if the limit is not set.
+    pub fn BridgeGetMemoryLimit(limit_kind: libc::c_int) -> libc::size_t;
+
+    /// Set the memory limit of `limit_kind`: 0 for the soft limit, 1 for the hard limit.
+    ///
+    /// TCMalloc releases memory to the system to stay under the limit. If the hard limit can not
+    /// be met, the process is aborted. `limit` of 0 or `size_t::MAX` removes the limit.
+    pub fn BridgeSetMemoryLimit(limit: libc::size_t, limit_kind: libc::c_int);
 }
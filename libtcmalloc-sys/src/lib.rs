@@ -14,6 +14,16 @@ mod extension;
 #[cfg_attr(docsrs, doc(cfg(feature = "extension")))]
 pub use extension::*;
 
+/// Pointer to the allocated memory and its capacity, which may be larger than the requested size.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct SizedPtr {
+    /// Pointer to the allocated memory or null if out of memory.
+    pub p: *mut core::ffi::c_void,
+    /// Capacity of the allocated memory in bytes or 0 if out of memory.
+    pub n: libc::size_t,
+}
+
 unsafe extern "C" {
     /// Allocate `size` bytes aligned by `alignment`.
     ///
@@ -26,6 +36,28 @@ unsafe extern "C" {
         alignment: libc::size_t,
     ) -> *mut core::ffi::c_void;
 
+    /// Allocate at least `size` bytes aligned by `alignment`.
+    ///
+    /// Return a pointer to the allocated memory and its capacity or `{null, 0}` if out of memory.
+    ///
+    /// The size passed to [`TCMallocInternalDeleteSizedAligned`] may be any value between `size`
+    /// and the returned capacity.
+    pub fn tcmalloc_size_returning_operator_new_aligned_nothrow(
+        size: libc::size_t,
+        alignment: libc::size_t,
+    ) -> SizedPtr;
+
+    /// Allocate at least `size` bytes aligned by `alignment` with the access frequency hint
+    /// `hot_cold`: 0 for rarely accessed memory up to 255 for very frequently accessed memory.
+    /// Rarely accessed memory is placed into separate cold pages.
+    ///
+    /// Return a pointer to the allocated memory and its capacity or `{null, 0}` if out of memory.
+    pub fn tcmalloc_size_returning_operator_new_aligned_hot_cold_nothrow(
+        size: libc::size_t,
+        alignment: libc::size_t,
+        hot_cold: u8,
+    ) -> SizedPtr;
+
     /// Free previously allocated memory.
     ///
     /// The pointer `ptr` must have been allocated before.
@@ -58,12 +90,75 @@ unsafe extern "C" {
     /// Returns null pointer if allocation failed or `new_size` is 0.
     ///
     /// `old_size` will be set to the original size of the memory block.
+    #[deprecated(
+        note = "resize in place with `BridgeGetSizeClassOf`, `BridgeGetSizeClassFor`, \
+                `BridgeGetResizablePagesSize`, `BridgeRoundUpToPages` and `BridgeCompleteResizeInPlace`"
+    )]
     pub fn BridgePrepareReallocAligned(
         old_ptr: *mut core::ffi::c_void,
         new_size: libc::size_t,
         alignment: libc::size_t,
         old_size: *mut libc::size_t,
     ) -> *mut core::ffi::c_void;
+
+    /// Get the size class of the allocation at `ptr` or 0 if it is allocated by pages.
+    ///
+    /// The pointer `ptr` must have been allocated before.
+    pub fn BridgeGetSizeClassOf(ptr: *const core::ffi::c_void) -> libc::size_t;
+
+    /// Get the size class which is used to free the allocation at `ptr` with `size` and
+    /// `alignment`, or 0 if such allocation is freed by pages.
+    ///
+    /// The pointer `ptr` must have been allocated before.
+    pub fn BridgeGetSizeClassFor(
+        ptr: *const core::ffi::c_void,
+        size: libc::size_t,
+        alignment: libc::size_t,
+    ) -> libc::size_t;
+
+    /// Get the size of the allocation at `ptr`, which is allocated by pages, or 0 if it can not
+    /// be resized in place.
+    ///
+    /// The pointer `ptr` must have been allocated before and must have size class 0.
+    pub fn BridgeGetResizablePagesSize(ptr: *const core::ffi::c_void) -> libc::size_t;
+
+    /// Round up `size` to the whole number of pages.
+    pub fn BridgeRoundUpToPages(size: libc::size_t) -> libc::size_t;
+
+    /// Complete resizing of the allocation at `ptr` to `new_size` in place: report the growth to
+    /// the sampler and invoke the hooks with the updated size, as `realloc` does.
+    ///
+    /// Return false without any changes if the growth would be sampled. The size class or
+    /// the number of pages of the allocation must be the same for `new_size`.
+    pub fn BridgeCompleteResizeInPlace(ptr: *mut core::ffi::c_void, new_size: libc::size_t)
+    -> bool;
+
+    /// Free the resources cached for `cpu` and return the number of freed bytes, or 0 if `cpu`
+    /// is negative or not less than the number of CPUs.
+    pub fn BridgeReleaseCpuMemory(cpu: libc::c_int) -> libc::size_t;
+
+    /// Allocate up to `count` objects of `size` and `alignment` into `batch`.
+    ///
+    /// Returns the number of allocated objects, which is less than `count` only when out of
+    /// memory.
+    ///
+    /// The `batch` must be valid for writes of `count` pointers.
+    pub fn BridgeAllocBatch(
+        size: libc::size_t,
+        alignment: libc::size_t,
+        batch: *mut *mut core::ffi::c_void,
+        count: libc::size_t,
+    ) -> libc::size_t;
+
+    /// Free `count` objects of `size` and `alignment` from `batch`.
+    ///
+    /// Every pointer in `batch` must have been allocated before with the `size` and `alignment`.
+    pub fn BridgeFreeBatch(
+        batch: *const *mut core::ffi::c_void,
+        count: libc::size_t,
+        size: libc::size_t,
+        alignment: libc::size_t,
+    );
 }
 
 #[cfg(test)]
@@ -75,4 +170,13 @@ mod tests {
         let ptr = unsafe { BridgeTCMallocInternalNewAlignedNothrow(8, 16) } as *mut u8;
         unsafe { TCMallocInternalDeleteSizedAligned(ptr as *mut libc::c_void, 8, 16) };
     }
+
+    #[test]
+    fn it_frees_size_returning_memory() {
+        let SizedPtr { p, n } =
+            unsafe { tcmalloc_size_returning_operator_new_aligned_nothrow(5, 16) };
+        assert!(!p.is_null());
+        assert!(n >= 5);
+        unsafe { TCMallocInternalDeleteSizedAligned(p, n, 16) };
+    }
 }
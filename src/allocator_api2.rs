@@ -0,0 +1,69 @@
+use crate::{ColdAlloc, TCMalloc, cold::COLD_HINT};
+use allocator_api2::alloc::{AllocError, Allocator};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ptr::NonNull,
+};
+
+unsafe impl Allocator for TCMalloc {
+    /// Allocates memory for the `layout`. The returned slice covers the whole size class, so its
+    /// length may be larger than `layout.size()`.
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        TCMalloc::alloc_with_size(layout)
+            .map(|(ptr, capacity)| NonNull::slice_from_raw_parts(ptr, capacity))
+            .ok_or(AllocError)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { GlobalAlloc::dealloc(self, ptr.as_ptr(), layout) };
+    }
+}
+
+unsafe impl Allocator for ColdAlloc {
+    /// Allocates memory for the `layout` in cold pages. The returned slice covers the whole size
+    /// class, so its length may be larger than `layout.size()`.
+    #[inline]
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        TCMalloc::alloc_hinted(layout, COLD_HINT)
+            .map(|(ptr, capacity)| NonNull::slice_from_raw_parts(ptr, capacity))
+            .ok_or(AllocError)
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { Allocator::deallocate(&TCMalloc, ptr, layout) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use allocator_api2::vec::Vec;
+
+    #[test]
+    fn test_allocate() {
+        let layout = Layout::from_size_align(100, 32).unwrap();
+        let ptr = Allocator::allocate(&TCMalloc, layout).unwrap();
+        assert!(ptr.len() >= layout.size());
+        assert_eq!(ptr.cast::<u8>().as_ptr() as usize % layout.align(), 0);
+        unsafe { Allocator::deallocate(&TCMalloc, ptr.cast(), layout) };
+    }
+
+    #[test]
+    fn test_vec_new_in() {
+        let mut vec = Vec::new_in(TCMalloc);
+        vec.extend(0..10_000u32);
+        vec.shrink_to_fit();
+        assert_eq!(vec.iter().sum::<u32>(), 49_995_000);
+    }
+
+    #[test]
+    fn test_cold_vec_new_in() {
+        let mut vec = Vec::new_in(ColdAlloc);
+        vec.extend(0..10_000u32);
+        vec.shrink_to_fit();
+        assert_eq!(vec.iter().sum::<u32>(), 49_995_000);
+    }
+}
@@ -0,0 +1,340 @@
+extern crate std;
+
+use crate::TCMalloc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use libtcmalloc_sys::BridgeSetBackgroundProcessActionsEnabled;
+#[cfg(target_os = "linux")]
+use std::vec::Vec;
+use std::{io, string::String, sync::mpsc, thread};
+
+/// Set while background actions started by this crate run, so at most one loop runs at a time.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Exclusive right to run background actions in the process, released on drop.
+///
+/// Background actions are enabled and disabled by the process-wide flag, so a new loop started
+/// while the previous one is stopping would keep the previous one running.
+pub(crate) struct Running(());
+
+impl Running {
+    /// Claims the right to run background actions, fails with [`io::ErrorKind::AlreadyExists`]
+    /// if they are already running.
+    pub(crate) fn acquire() -> io::Result<Self> {
+        RUNNING
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .map(|_| Running(()))
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "background actions are already running",
+                )
+            })
+    }
+
+    /// Runs background actions until they are disabled, then releases the right to run them.
+    pub(crate) fn run(self) {
+        TCMalloc::process_background_actions();
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::Release);
+    }
+}
+
+/// Disables background actions, so the loop run by the owner of [`Running`] returns within
+/// the background process sleep interval.
+pub(crate) fn stop() {
+    unsafe { BridgeSetBackgroundProcessActionsEnabled(false) };
+}
+
+/// Handle of the thread running [`TCMalloc::process_background_actions`].
+///
+/// Background actions are stopped and the thread is joined when the handle is dropped or
+/// [`BackgroundActions::stop`] is called. Only one handle, thread started by
+/// [`TCMalloc::process_background_actions_thread`] or task of the `tokio` integration runs
+/// background actions at a time.
+///
+/// # Example
+/// ```rust
+/// use tcmalloc_better::{BackgroundActions, TCMalloc};
+///
+/// #[global_allocator]
+/// static GLOBAL: TCMalloc = TCMalloc;
+///
+/// let background_actions = BackgroundActions::start().unwrap();
+///
+/// // Rest of main
+///
+/// drop(background_actions);
+/// ```
+#[derive(Debug)]
+#[must_use = "background actions are stopped when the handle is dropped"]
+pub struct BackgroundActions {
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl BackgroundActions {
+    /// Enables background actions and runs them in a new thread named `tcmalloc-bg`, or returns
+    /// `Ok(None)` if they are not needed on this platform.
+    ///
+    /// Fails with [`io::ErrorKind::AlreadyExists`] if background actions are already running,
+    /// including a previous handle which is still stopping. Use [`BackgroundActionsBuilder`] to
+    /// configure the thread.
+    pub fn start() -> io::Result<Option<Self>> {
+        BackgroundActionsBuilder::new().spawn()
+    }
+
+    /// Disables background actions and waits for the thread to finish, which takes up to
+    /// the background process sleep interval.
+    pub fn stop(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            stop();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for BackgroundActions {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+/// Scheduling priority of the background actions thread, see
+/// [`BackgroundActionsBuilder::priority`].
+#[cfg(target_os = "linux")]
+#[cfg_attr(docsrs, doc(cfg(target_os = "linux")))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// Nice value from -20 (the highest priority) to 19 (the lowest priority) with the default
+    /// `SCHED_OTHER` policy. Values lower than the current one require `CAP_SYS_NICE`.
+    Nice(i32),
+    /// `SCHED_IDLE` policy, the thread runs only when the CPU has nothing else to run.
+    Idle,
+}
+
+/// Thread configuration of [`BackgroundActions`].
+///
+/// # Example
+/// ```rust,no_run
+/// use tcmalloc_better::{BackgroundActionsBuilder, Priority};
+///
+/// // Keep the allocator housekeeping on the CPU 0, off the isolated cores.
+/// let background_actions = BackgroundActionsBuilder::new()
+///     .affinity([0])
+///     .priority(Priority::Idle)
+///     .spawn()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct BackgroundActionsBuilder {
+    name: String,
+    stack_size: Option<usize>,
+    #[cfg(target_os = "linux")]
+    affinity: Option<Vec<usize>>,
+    #[cfg(target_os = "linux")]
+    priority: Option<Priority>,
+}
+
+impl Default for BackgroundActionsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BackgroundActionsBuilder {
+    /// Creates the builder of a thread named `tcmalloc-bg` with the default stack size, affinity
+    /// and priority.
+    pub fn new() -> Self {
+        Self {
+            name: "tcmalloc-bg".into(),
+            stack_size: None,
+            #[cfg(target_os = "linux")]
+            affinity: None,
+            #[cfg(target_os = "linux")]
+            priority: None,
+        }
+    }
+
+    /// Sets the name of the thread.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the stack size of the thread in bytes, see [`thread::Builder::stack_size`].
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Restricts the thread to the `cpus` with `sched_setaffinity`.
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(docsrs, doc(cfg(target_os = "linux")))]
+    pub fn affinity(mut self, cpus: impl IntoIterator<Item = usize>) -> Self {
+        self.affinity = Some(cpus.into_iter().collect());
+        self
+    }
+
+    /// Sets the scheduling priority of the thread.
+    #[cfg(target_os = "linux")]
+    #[cfg_attr(docsrs, doc(cfg(target_os = "linux")))]
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Enables background actions and runs them in a new thread, or returns `Ok(None)` if they
+    /// are not needed on this platform.
+    ///
+    /// Fails with [`io::ErrorKind::AlreadyExists`] if background actions are already running, or
+    /// if the thread can not be spawned or configured, in that case background actions are not
+    /// started.
+    pub fn spawn(self) -> io::Result<Option<BackgroundActions>> {
+        if !TCMalloc::needs_process_background_actions() {
+            return Ok(None);
+        }
+        #[cfg(target_os = "linux")]
+        let cpu_set = self.affinity.as_deref().map(cpu_set).transpose()?;
+        let running = Running::acquire()?;
+
+        let mut builder = thread::Builder::new().name(self.name);
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        // The thread is configured before background actions are enabled, so they are not
+        // started if that fails.
+        let (configured_sender, configured) = mpsc::sync_channel(1);
+        let (start_sender, start) = mpsc::sync_channel(1);
+        #[cfg(target_os = "linux")]
+        let priority = self.priority;
+        let thread = builder.spawn(move || {
+            #[cfg(target_os = "linux")]
+            let result = configure(cpu_set.as_ref(), priority);
+            #[cfg(not(target_os = "linux"))]
+            let result = Ok(());
+            let ok = result.is_ok();
+            let _ = configured_sender.send(result);
+            if ok && start.recv() == Ok(()) {
+                running.run();
+            }
+        })?;
+        match configured.recv() {
+            Ok(Ok(())) => {
+                unsafe { BridgeSetBackgroundProcessActionsEnabled(true) };
+                let _ = start_sender.send(());
+                Ok(Some(BackgroundActions {
+                    thread: Some(thread),
+                }))
+            }
+            Ok(Err(err)) => {
+                let _ = thread.join();
+                Err(err)
+            }
+            Err(_) => {
+                let _ = thread.join();
+                Err(io::Error::other("background actions thread panicked"))
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn cpu_set(cpus: &[usize]) -> io::Result<libc::cpu_set_t> {
+    let mut cpu_set = unsafe { core::mem::zeroed::<libc::cpu_set_t>() };
+    for &cpu in cpus {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                std::format!("CPU {cpu} is out of the CPU set"),
+            ));
+        }
+        unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+    }
+    Ok(cpu_set)
+}
+
+/// Applies the affinity and the priority to the current thread.
+#[cfg(target_os = "linux")]
+fn configure(cpu_set: Option<&libc::cpu_set_t>, priority: Option<Priority>) -> io::Result<()> {
+    if let Some(cpu_set) = cpu_set {
+        if unsafe { libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), cpu_set) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    let result = match priority {
+        None => 0,
+        Some(Priority::Nice(nice)) => unsafe {
+            libc::setpriority(libc::PRIO_PROCESS, libc::gettid() as libc::id_t, nice)
+        },
+        Some(Priority::Idle) => unsafe {
+            libc::sched_setscheduler(
+                0,
+                libc::SCHED_IDLE,
+                &libc::sched_param { sched_priority: 0 },
+            )
+        },
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Serializes tests which enable and disable background actions process-wide.
+#[cfg(test)]
+pub(crate) static TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background_actions() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let Some(background_actions) = BackgroundActions::start().unwrap() else {
+            return;
+        };
+        assert_eq!(
+            BackgroundActions::start().unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert!(TCMalloc::process_background_actions_thread().is_none());
+        background_actions.stop();
+
+        BackgroundActions::start().unwrap().unwrap().stop();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_background_actions_builder() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let cpu = unsafe { libc::sched_getcpu() } as usize;
+        let background_actions = BackgroundActionsBuilder::new()
+            .name("tcmalloc-bg-test")
+            .stack_size(1 << 20)
+            .affinity([cpu])
+            .priority(Priority::Nice(19))
+            .spawn()
+            .unwrap()
+            .unwrap();
+        let thread = background_actions.thread.as_ref().unwrap().thread();
+        assert_eq!(thread.name(), Some("tcmalloc-bg-test"));
+        drop(background_actions);
+
+        let err = BackgroundActionsBuilder::new()
+            .affinity([usize::MAX])
+            .spawn()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        // The failed spawn does not keep the right to run background actions.
+        BackgroundActions::start().unwrap().unwrap().stop();
+    }
+}
@@ -0,0 +1,192 @@
+extern crate std;
+
+use crate::{LimitKind, TCMalloc};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Mount point of the cgroup v2 hierarchy or of the cgroup v1 controllers.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Limits at or above this value are treated as no limit: cgroup v1 reports a missing limit
+/// as the largest page-aligned `i64`.
+const UNLIMITED: u64 = 1 << 62;
+
+impl TCMalloc {
+    /// Sets the soft memory limit to `fraction` of the memory limit of the current process's
+    /// cgroup and returns the applied limit in bytes, or `None` if the cgroup has no memory limit.
+    ///
+    /// The cgroup limit is the smallest limit of the cgroup and its ancestors, read from
+    /// `memory.max` for cgroup v2 or from `memory.limit_in_bytes` of the memory controller
+    /// for cgroup v1, both mounted under `/sys/fs/cgroup`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fraction` is not a positive finite number.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use tcmalloc_better::TCMalloc;
+    ///
+    /// // Start releasing memory at 90% of the container memory limit.
+    /// TCMalloc::apply_cgroup_memory_limit(0.9).unwrap();
+    /// ```
+    pub fn apply_cgroup_memory_limit(fraction: f64) -> io::Result<Option<usize>> {
+        assert!(
+            fraction.is_finite() && fraction > 0.,
+            "fraction must be a positive finite number, got {fraction}"
+        );
+        let proc_self_cgroup = match fs::read_to_string("/proc/self/cgroup") {
+            Ok(proc_self_cgroup) => proc_self_cgroup,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let Some(limit) = cgroup_memory_limit(Path::new(CGROUP_ROOT), &proc_self_cgroup)? else {
+            return Ok(None);
+        };
+        let soft_limit = ((limit as f64 * fraction) as usize).max(1);
+        TCMalloc::set_memory_limit(soft_limit, LimitKind::Soft);
+        Ok(Some(soft_limit))
+    }
+}
+
+/// Returns the memory limit of the cgroup described by `proc_self_cgroup` (in the format of
+/// `/proc/self/cgroup`) with the cgroup filesystem mounted at `root`.
+///
+/// The cgroup v1 memory controller takes precedence in the hybrid hierarchy.
+fn cgroup_memory_limit(root: &Path, proc_self_cgroup: &str) -> io::Result<Option<u64>> {
+    let mut unified = None;
+    for line in proc_self_cgroup.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(id), Some(controllers), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if controllers
+            .split(',')
+            .any(|controller| controller == "memory")
+        {
+            return min_limit(&root.join("memory"), path, "memory.limit_in_bytes");
+        }
+        if id == "0" && controllers.is_empty() {
+            unified = Some(path);
+        }
+    }
+    match unified {
+        Some(path) => min_limit(root, path, "memory.max"),
+        None => Ok(None),
+    }
+}
+
+/// Returns the smallest limit in `file` of the cgroup at `path` and its ancestors.
+///
+/// Without the cgroup namespace, the cgroup path is not visible in the container and
+/// the container's own cgroup is mounted at `root`, so missing files are skipped.
+fn min_limit(root: &Path, path: &str, file: &str) -> io::Result<Option<u64>> {
+    let dir = if path.split('/').any(|component| component == "..") {
+        PathBuf::from(root)
+    } else {
+        root.join(path.trim_start_matches('/'))
+    };
+    let mut min = None;
+    for dir in dir.ancestors().take_while(|dir| dir.starts_with(root)) {
+        if let Some(limit) = read_limit(&dir.join(file))? {
+            min = Some(min.map_or(limit, |min: u64| min.min(limit)));
+        }
+    }
+    Ok(min)
+}
+
+/// Reads the limit from `file`, `max` and missing files are treated as no limit.
+fn read_limit(file: &Path) -> io::Result<Option<u64>> {
+    let contents = match fs::read_to_string(file) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    match contents.trim() {
+        "max" => Ok(None),
+        limit => match limit.parse::<u64>() {
+            Ok(limit) if limit < UNLIMITED => Ok(Some(limit)),
+            Ok(_) => Ok(None),
+            Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{format, string::ToString};
+
+    struct TempRoot(PathBuf);
+
+    impl TempRoot {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "tcmalloc-better-cgroup-{}-{name}",
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&root);
+            Self(root)
+        }
+
+        fn write(&self, file: &str, contents: &str) {
+            let file = self.0.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, contents).unwrap();
+        }
+    }
+
+    impl Drop for TempRoot {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_cgroup_v2_memory_limit() {
+        let root = TempRoot::new("v2");
+        root.write("kubepods/memory.max", "1073741824\n");
+        root.write("kubepods/pod/memory.max", "max\n");
+        root.write("kubepods/pod/container/memory.max", "2147483648\n");
+
+        let limit =
+            |proc_self_cgroup: &str| cgroup_memory_limit(&root.0, proc_self_cgroup).unwrap();
+        assert_eq!(limit("0::/kubepods/pod/container\n"), Some(1 << 30));
+        assert_eq!(limit("0::/kubepods/pod\n"), Some(1 << 30));
+        assert_eq!(limit("0::/\n"), None);
+        assert_eq!(limit(""), None);
+
+        // The container's own cgroup is mounted at the root without the cgroup namespace.
+        root.write("memory.max", "536870912\n");
+        assert_eq!(limit("0::/system.slice/docker-1.scope\n"), Some(1 << 29));
+        assert_eq!(limit("0::/../..\n"), Some(1 << 29));
+
+        root.write("memory.max", "invalid\n");
+        assert_eq!(
+            cgroup_memory_limit(&root.0, "0::/").unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_cgroup_v1_memory_limit() {
+        let root = TempRoot::new("v1");
+        root.write(
+            "memory/memory.limit_in_bytes",
+            &(i64::MAX as u64 & !4095).to_string(),
+        );
+        root.write("memory/docker/1/memory.limit_in_bytes", "268435456\n");
+
+        let limit =
+            |proc_self_cgroup: &str| cgroup_memory_limit(&root.0, proc_self_cgroup).unwrap();
+        assert_eq!(
+            limit("12:cpu,cpuacct:/docker/1\n11:memory:/docker/1\n0::/docker/1\n"),
+            Some(1 << 28)
+        );
+        assert_eq!(limit("11:memory:/\n"), None);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::TCMalloc;
+use core::alloc::{GlobalAlloc, Layout};
+
+/// Access frequency hint for rarely accessed memory.
+pub(crate) const COLD_HINT: u8 = 0;
+
+/// A memory allocator which places all allocations into cold pages.
+///
+/// Use it for large, rarely accessed data, so it does not occupy hugepages of frequently accessed
+/// memory. Memory allocated by [`ColdAlloc`] may be freed by [`TCMalloc`] and vice versa.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ColdAlloc;
+
+unsafe impl GlobalAlloc for ColdAlloc {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        TCMalloc::alloc_hinted(layout, COLD_HINT)
+            .map_or(core::ptr::null_mut(), |(ptr, _)| ptr.as_ptr())
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { TCMalloc.dealloc(ptr, layout) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_frees_cold_memory() {
+        unsafe {
+            let layout = Layout::from_size_align(1 << 20, 32).unwrap();
+            let alloc = ColdAlloc;
+
+            let ptr = alloc.alloc(layout);
+            assert!(!ptr.is_null());
+            let ptr = alloc.realloc(ptr, layout, 2 << 20);
+            assert!(!ptr.is_null());
+            alloc.dealloc(ptr, Layout::from_size_align(2 << 20, 32).unwrap());
+        }
+    }
+}
@@ -1,11 +1,202 @@
 use crate::TCMalloc;
+use core::{alloc::Layout, marker::PhantomData, time::Duration};
 use libtcmalloc_sys::{NeedsProcessBackgroundActions, ProcessBackgroundActions};
 #[cfg(feature = "std")]
-use std::thread;
+use std::{collections::BTreeMap, string::String, thread, vec::Vec};
 
 #[cfg(feature = "std")]
 extern crate std;
 
+/// Well-known numeric properties of TCMalloc.
+///
+/// See [`TCMalloc::numeric_property`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum NumericProperty {
+    /// Number of bytes currently allocated by application.
+    CurrentAllocatedBytes,
+    /// Number of bytes in the heap ==
+    /// current_allocated_bytes + fragmentation + freed (but not released to OS) memory regions.
+    HeapSize,
+    /// Overall virtual memory used, including malloc internals.
+    VirtualMemoryUsed,
+    /// Overall physical memory used, including malloc internals.
+    PhysicalMemoryUsed,
+    /// Peak of backed bytes.
+    PeakMemoryUsage,
+    /// Fragmentation at peak memory usage, in percents.
+    RealizedFragmentation,
+    /// Upper limit on total number of bytes stored across all per-thread caches.
+    MaxTotalThreadCacheBytes,
+    /// Number of bytes used across all thread caches.
+    CurrentTotalThreadCacheBytes,
+    /// Number of thread caches in use.
+    ThreadCacheCount,
+    /// Number of bytes in free, mapped pages in page heap.
+    PageheapFreeBytes,
+    /// Number of bytes in free, unmapped pages in page heap.
+    PageheapUnmappedBytes,
+    /// Sum of [`Self::PageheapFreeBytes`] and [`Self::PageheapUnmappedBytes`].
+    SlackBytes,
+    /// Number of bytes in per-CPU free-lists.
+    CpuFree,
+    /// Number of bytes in central cache.
+    CentralCacheFree,
+    /// Number of bytes in cross-thread transfer caches.
+    TransferCacheFree,
+    /// Number of bytes in sharded transfer caches.
+    ShardedTransferCacheFree,
+    /// Number of bytes used by internal data structures.
+    MetadataBytes,
+    /// Number of bytes in per-CPU, per-thread and transfer caches.
+    LocalBytes,
+    /// Number of bytes of external fragmentation.
+    ExternalFragmentationBytes,
+    /// Estimate of the internal fragmentation based on sampled allocations.
+    SampledInternalFragmentation,
+    /// Number of bytes required by the application including the allocator overhead.
+    RequiredBytes,
+    /// Whether TCMalloc is using per-CPU caches (1 or 0 respectively).
+    PerCpuCachesActive,
+    /// Soft memory limit in bytes.
+    DesiredUsageLimitBytes,
+    /// Hard memory limit in bytes.
+    HardUsageLimitBytes,
+    /// Number of times the soft memory limit was hit.
+    SoftLimitHits,
+    /// Number of times the hard memory limit was hit.
+    HardLimitHits,
+    /// Number of successful shrinks after the soft memory limit was hit.
+    SuccessfulShrinksAfterSoftLimitHit,
+    /// Number of successful shrinks after the hard memory limit was hit.
+    SuccessfulShrinksAfterHardLimitHit,
+    /// Total number of bytes released to OS.
+    NumReleasedTotalBytes,
+    /// Number of bytes released to OS by explicit release calls.
+    NumReleasedReleaseMemoryToSystemBytes,
+    /// Number of bytes released to OS by background actions.
+    NumReleasedProcessBackgroundActionsBytes,
+    /// Number of bytes released to OS because the soft memory limit was exceeded.
+    NumReleasedSoftLimitExceededBytes,
+    /// Number of bytes released to OS because the hard memory limit was exceeded.
+    NumReleasedHardLimitExceededBytes,
+}
+
+impl NumericProperty {
+    /// Property name as TCMalloc knows it.
+    pub const fn name(self) -> &'static str {
+        match self {
+            NumericProperty::CurrentAllocatedBytes => "generic.current_allocated_bytes",
+            NumericProperty::HeapSize => "generic.heap_size",
+            NumericProperty::VirtualMemoryUsed => "generic.virtual_memory_used",
+            NumericProperty::PhysicalMemoryUsed => "generic.physical_memory_used",
+            NumericProperty::PeakMemoryUsage => "generic.peak_memory_usage",
+            NumericProperty::RealizedFragmentation => "generic.realized_fragmentation",
+            NumericProperty::MaxTotalThreadCacheBytes => "tcmalloc.max_total_thread_cache_bytes",
+            NumericProperty::CurrentTotalThreadCacheBytes => {
+                "tcmalloc.current_total_thread_cache_bytes"
+            }
+            NumericProperty::ThreadCacheCount => "tcmalloc.thread_cache_count",
+            NumericProperty::PageheapFreeBytes => "tcmalloc.pageheap_free_bytes",
+            NumericProperty::PageheapUnmappedBytes => "tcmalloc.pageheap_unmapped_bytes",
+            NumericProperty::SlackBytes => "tcmalloc.slack_bytes",
+            NumericProperty::CpuFree => "tcmalloc.cpu_free",
+            NumericProperty::CentralCacheFree => "tcmalloc.central_cache_free",
+            NumericProperty::TransferCacheFree => "tcmalloc.transfer_cache_free",
+            NumericProperty::ShardedTransferCacheFree => "tcmalloc.sharded_transfer_cache_free",
+            NumericProperty::MetadataBytes => "tcmalloc.metadata_bytes",
+            NumericProperty::LocalBytes => "tcmalloc.local_bytes",
+            NumericProperty::ExternalFragmentationBytes => "tcmalloc.external_fragmentation_bytes",
+            NumericProperty::SampledInternalFragmentation => {
+                "tcmalloc.sampled_internal_fragmentation"
+            }
+            NumericProperty::RequiredBytes => "tcmalloc.required_bytes",
+            NumericProperty::PerCpuCachesActive => "tcmalloc.per_cpu_caches_active",
+            NumericProperty::DesiredUsageLimitBytes => "tcmalloc.desired_usage_limit_bytes",
+            NumericProperty::HardUsageLimitBytes => "tcmalloc.hard_usage_limit_bytes",
+            NumericProperty::SoftLimitHits => "tcmalloc.soft_limit_hits",
+            NumericProperty::HardLimitHits => "tcmalloc.hard_limit_hits",
+            NumericProperty::SuccessfulShrinksAfterSoftLimitHit => {
+                "tcmalloc.successful_shrinks_after_soft_limit_hit"
+            }
+            NumericProperty::SuccessfulShrinksAfterHardLimitHit => {
+                "tcmalloc.successful_shrinks_after_hard_limit_hit"
+            }
+            NumericProperty::NumReleasedTotalBytes => "tcmalloc.num_released_total_bytes",
+            NumericProperty::NumReleasedReleaseMemoryToSystemBytes => {
+                "tcmalloc.num_released_release_memory_to_system_bytes"
+            }
+            NumericProperty::NumReleasedProcessBackgroundActionsBytes => {
+                "tcmalloc.num_released_process_background_actions_bytes"
+            }
+            NumericProperty::NumReleasedSoftLimitExceededBytes => {
+                "tcmalloc.num_released_soft_limit_exceeded_bytes"
+            }
+            NumericProperty::NumReleasedHardLimitExceededBytes => {
+                "tcmalloc.num_released_hard_limit_exceeded_bytes"
+            }
+        }
+    }
+}
+
+impl AsRef<str> for NumericProperty {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.name()
+    }
+}
+
+/// Ownership of the memory, see [`TCMalloc::ownership`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Ownership {
+    /// Ownership can not be determined.
+    Unknown,
+    /// Memory is allocated by TCMalloc.
+    Owned,
+    /// Memory is allocated elsewhere or the pointer is null.
+    NotOwned,
+}
+
+/// Rate of releasing memory to the system, see [`TCMalloc::set_background_release_rate`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BytesPerSecond(pub usize);
+
+/// Marks the current thread as idle while alive and as busy on drop, see
+/// [`TCMalloc::idle_guard`].
+#[derive(Debug)]
+#[must_use = "the thread is marked as busy when the guard is dropped"]
+pub struct IdleGuard {
+    // The guard marks the thread which created it.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for IdleGuard {
+    fn drop(&mut self) {
+        TCMalloc::mark_thread_busy();
+    }
+}
+
+/// Kind of the memory limit, see [`TCMalloc::set_memory_limit`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LimitKind {
+    /// TCMalloc releases memory to the system to stay under the limit if possible, but
+    /// allocations which exceed it still succeed.
+    Soft,
+    /// Same as the soft limit, but the process is aborted if memory can not be released to stay
+    /// under the limit.
+    Hard,
+}
+
+impl LimitKind {
+    #[inline]
+    fn as_raw(self) -> core::ffi::c_int {
+        match self {
+            LimitKind::Soft => 0,
+            LimitKind::Hard => 1,
+        }
+    }
+}
+
 #[cfg(feature = "extension")]
 #[cfg_attr(docsrs, doc(cfg(feature = "extension")))]
 impl TCMalloc {
@@ -17,24 +208,70 @@ impl TCMalloc {
 
     /// Runs housekeeping actions for the allocator off of the main allocation path.
     ///
-    /// Should be run in the background thread. May return or may not return.
-    /// Use `process_background_actions_thread()` if possible.
+    /// Should be run in the background thread. Returns only when background actions are disabled.
+    /// Use `process_background_actions_thread()` or [`BackgroundActions`](crate::BackgroundActions)
+    /// if possible.
+    ///
+    /// Background actions were not linked in earlier releases and this function returned
+    /// immediately.
     #[inline]
     pub fn process_background_actions() {
         unsafe { ProcessBackgroundActions() };
     }
 
     /// Runs housekeeping actions for the allocator in the background thread.
+    ///
+    /// The thread is detached and runs for the rest of the process, use
+    /// [`BackgroundActions`](crate::BackgroundActions) to stop and join it. Returns `None` if
+    /// background actions are not needed on this platform or are already running.
     #[cfg(feature = "std")]
     #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
     pub fn process_background_actions_thread() -> Option<thread::JoinHandle<()>> {
-        if Self::needs_process_background_actions() {
-            Some(thread::spawn(|| {
-                Self::process_background_actions();
-            }))
-        } else {
-            None
+        if !Self::needs_process_background_actions() {
+            return None;
         }
+        let running = crate::background::Running::acquire().ok()?;
+        unsafe { libtcmalloc_sys::BridgeSetBackgroundProcessActionsEnabled(true) };
+        Some(thread::spawn(move || running.run()))
+    }
+
+    /// The shortest interval between background actions accepted by
+    /// [`set_background_process_sleep_interval`](TCMalloc::set_background_process_sleep_interval).
+    pub const MIN_BACKGROUND_PROCESS_SLEEP_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// Returns the interval between background actions.
+    #[inline]
+    pub fn background_process_sleep_interval() -> Duration {
+        let nanoseconds = unsafe { libtcmalloc_sys::BridgeGetBackgroundProcessSleepInterval() };
+        Duration::from_nanos(nanoseconds.max(0) as u64)
+    }
+
+    /// Sets the interval between background actions, which also scales the periods of
+    /// the less frequent actions (e.g. per-CPU caches are reclaimed every 30 intervals).
+    ///
+    /// Longer intervals make background actions less intrusive at the cost of slower release
+    /// of unused memory. Intervals shorter than
+    /// [`MIN_BACKGROUND_PROCESS_SLEEP_INTERVAL`](TCMalloc::MIN_BACKGROUND_PROCESS_SLEEP_INTERVAL)
+    /// are raised to it, zero interval would make background actions spin.
+    #[inline]
+    pub fn set_background_process_sleep_interval(interval: Duration) {
+        let interval = interval.max(Self::MIN_BACKGROUND_PROCESS_SLEEP_INTERVAL);
+        let nanoseconds = i64::try_from(interval.as_nanos()).unwrap_or(i64::MAX);
+        unsafe { libtcmalloc_sys::BridgeSetBackgroundProcessSleepInterval(nanoseconds) };
+    }
+
+    /// Returns the rate at which background actions release free memory from the page heap to
+    /// the system.
+    #[inline]
+    pub fn background_release_rate() -> BytesPerSecond {
+        BytesPerSecond(unsafe { libtcmalloc_sys::BridgeGetBackgroundReleaseRate() })
+    }
+
+    /// Sets the rate at which background actions release free memory from the page heap to
+    /// the system, zero disables the release.
+    #[inline]
+    pub fn set_background_release_rate(rate: BytesPerSecond) {
+        unsafe { libtcmalloc_sys::BridgeSetBackgroundReleaseRate(rate.0) };
     }
 
     /// Sets the maximum cache size per CPU cache. This is a per-core limit.
@@ -42,6 +279,196 @@ impl TCMalloc {
     pub fn set_max_per_cpu_cache_size(value: i32) {
         unsafe { libtcmalloc_sys::TCMalloc_Internal_SetMaxPerCpuCacheSize(value) };
     }
+
+    /// Tries to release at least `bytes` of free memory back to the system and returns the
+    /// number of released bytes, which is at most `bytes`.
+    ///
+    /// More memory may be released at once, the excess is accounted towards the following calls.
+    /// Less memory is released if there is not enough free memory or it is fragmented.
+    #[inline]
+    pub fn release_memory_to_system(bytes: usize) -> usize {
+        unsafe { libtcmalloc_sys::MallocExtension_Internal_ReleaseMemoryToSystem(bytes) }
+    }
+
+    /// Frees the memory cached for the `cpu` in the per-CPU cache and returns the number of freed
+    /// bytes. May be called from any CPU.
+    ///
+    /// Returns 0 if per-CPU caches are not used or `cpu` is negative or not less than the number
+    /// of possible CPUs.
+    #[inline]
+    pub fn release_cpu_memory(cpu: i32) -> usize {
+        unsafe { libtcmalloc_sys::BridgeReleaseCpuMemory(cpu) }
+    }
+
+    /// Marks the current thread as idle, a hint to release its thread-specific resources.
+    ///
+    /// The thread cache is returned to the central free lists with the `deprecated_perthread`
+    /// feature, per-CPU caches are not affected. It may be expensive, so it should not be called
+    /// too often. Avoid allocating between this call and going to sleep.
+    #[inline]
+    pub fn mark_thread_idle() {
+        unsafe { libtcmalloc_sys::MallocExtension_Internal_MarkThreadIdle() }
+    }
+
+    /// Marks the current thread as busy after [`mark_thread_idle`](TCMalloc::mark_thread_idle),
+    /// when it is going to do more work.
+    #[inline]
+    pub fn mark_thread_busy() {
+        unsafe { libtcmalloc_sys::MallocExtension_Internal_MarkThreadBusy() }
+    }
+
+    /// Marks the current thread as idle until the returned guard is dropped.
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::sync::mpsc;
+    /// use tcmalloc_better::TCMalloc;
+    ///
+    /// #[global_allocator]
+    /// static GLOBAL: TCMalloc = TCMalloc;
+    ///
+    /// let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    /// let worker = std::thread::spawn(move || {
+    ///     loop {
+    ///         let idle = TCMalloc::idle_guard();
+    ///         let Ok(job) = receiver.recv() else { break };
+    ///         drop(idle);
+    ///         assert_eq!(job.len(), 1024);
+    ///     }
+    /// });
+    /// sender.send(vec![0; 1024]).unwrap();
+    /// drop(sender);
+    /// worker.join().unwrap();
+    /// ```
+    pub fn idle_guard() -> IdleGuard {
+        Self::mark_thread_idle();
+        IdleGuard {
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Gets the named property's value or `None` if the property is not valid.
+    ///
+    /// Use [`NumericProperty`] for the well-known property names:
+    /// ```rust
+    /// use tcmalloc_better::{NumericProperty, TCMalloc};
+    ///
+    /// let heap_size = TCMalloc::numeric_property(NumericProperty::HeapSize);
+    /// let allocated = TCMalloc::numeric_property("generic.current_allocated_bytes");
+    /// ```
+    #[inline]
+    pub fn numeric_property(name: impl AsRef<str>) -> Option<usize> {
+        let name = name.as_ref();
+        let mut value = 0;
+        unsafe {
+            libtcmalloc_sys::BridgeGetNumericProperty(name.as_ptr().cast(), name.len(), &mut value)
+        }
+        .then_some(value)
+    }
+
+    /// Returns a human-readable description of the current state of the malloc data structures.
+    ///
+    /// See [TCMalloc docs](https://github.com/google/tcmalloc/blob/master/docs/stats.md) for how to
+    /// interpret these statistics.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn stats_text() -> String {
+        let mut buffer = Vec::<u8>::with_capacity(1 << 18);
+        loop {
+            let capacity = buffer.capacity();
+            let len =
+                unsafe { libtcmalloc_sys::BridgeGetStats(buffer.as_mut_ptr().cast(), capacity) };
+            if len <= capacity {
+                unsafe { buffer.set_len(len) };
+                break;
+            }
+            // The report may grow between calls, so leave some headroom for the next attempt
+            buffer.reserve_exact(len + len / 8);
+        }
+        String::from_utf8(buffer)
+            .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned())
+    }
+
+    /// Returns detailed statistics about the state of TCMalloc. The map is keyed by the name of
+    /// the statistic.
+    ///
+    /// Unlike [`TCMalloc::numeric_property`], this includes every statistic TCMalloc reports,
+    /// e.g. `tcmalloc.experiment.NAME` entries for running experiments.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn properties() -> BTreeMap<String, u64> {
+        unsafe extern "C" fn insert_property(
+            context: *mut core::ffi::c_void,
+            name_data: *const core::ffi::c_char,
+            name_size: usize,
+            value: usize,
+        ) {
+            let properties = unsafe { &mut *context.cast::<BTreeMap<String, u64>>() };
+            let name = unsafe { core::slice::from_raw_parts(name_data.cast::<u8>(), name_size) };
+            properties.insert(String::from_utf8_lossy(name).into_owned(), value as u64);
+        }
+
+        let mut properties = BTreeMap::new();
+        unsafe {
+            libtcmalloc_sys::BridgeGetProperties(
+                insert_property,
+                (&mut properties as *mut BTreeMap<String, u64>).cast(),
+            )
+        };
+        properties
+    }
+
+    /// Returns the number of bytes which will be allocated for the `layout`.
+    ///
+    /// The result is at least `layout.size()` and is rounded up to the size class, so it can be
+    /// used to grow buffers without wasting the tail of the allocation.
+    #[inline]
+    pub fn good_size(layout: Layout) -> usize {
+        unsafe { libtcmalloc_sys::BridgeGetEstimatedAllocatedSize(layout.size(), layout.align()) }
+    }
+
+    /// Returns whether the memory pointed to by `ptr` is allocated by TCMalloc.
+    ///
+    /// The result is reliable only for pointers returned by an allocation which is not freed yet.
+    /// Memory from other allocators may be reported as [`Ownership::Unknown`] or
+    /// [`Ownership::NotOwned`] but never as [`Ownership::Owned`].
+    #[inline]
+    pub fn ownership(ptr: *const u8) -> Ownership {
+        match unsafe { libtcmalloc_sys::BridgeGetOwnership(ptr.cast()) } {
+            1 => Ownership::Owned,
+            2 => Ownership::NotOwned,
+            _ => Ownership::Unknown,
+        }
+    }
+
+    /// Returns the number of bytes usable by the allocation at `ptr`, which is at least
+    /// the requested size, or `None` if `ptr` is not allocated by TCMalloc.
+    ///
+    /// # Safety
+    ///
+    /// If `ptr` is owned by TCMalloc, it must be the pointer returned by the allocation
+    /// (not an interior pointer) and the allocation must not be freed concurrently.
+    #[inline]
+    pub unsafe fn allocated_size(ptr: *const u8) -> Option<usize> {
+        let mut size = 0;
+        unsafe { libtcmalloc_sys::BridgeGetAllocatedSize(ptr.cast(), &mut size) }.then_some(size)
+    }
+
+    /// Returns the memory limit of the `kind` in bytes or `usize::MAX` if the limit is not set.
+    #[inline]
+    pub fn memory_limit(kind: LimitKind) -> usize {
+        unsafe { libtcmalloc_sys::BridgeGetMemoryLimit(kind.as_raw()) }
+    }
+
+    /// Sets the memory limit of the `kind` in bytes, `0` or `usize::MAX` removes the limit.
+    ///
+    /// When satisfying an allocation would exceed the limit, TCMalloc releases as much memory
+    /// to the system as needed to stay under it. See [`LimitKind`] for what happens when it
+    /// is not possible.
+    #[inline]
+    pub fn set_memory_limit(bytes: usize, kind: LimitKind) {
+        unsafe { libtcmalloc_sys::BridgeSetMemoryLimit(bytes, kind.as_raw()) };
+    }
 }
 
 #[cfg(test)]
@@ -51,12 +478,186 @@ mod tests {
     #[test]
     #[cfg(target_os = "linux")]
     fn test_needs_process_background_actions() {
-        assert!(!TCMalloc::needs_process_background_actions());
+        // Background actions were not linked in earlier releases, so this was false. They are
+        // linked now and `process_background_actions` loops instead of returning immediately.
+        assert!(TCMalloc::needs_process_background_actions());
     }
 
     #[test]
     #[cfg(feature = "std")]
     fn test_process_background_actions() {
-        TCMalloc::process_background_actions_thread();
+        let _lock = crate::background::TEST_LOCK
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if let Some(thread) = TCMalloc::process_background_actions_thread() {
+            // Stop the thread, so other tests may run background actions.
+            unsafe { libtcmalloc_sys::BridgeSetBackgroundProcessActionsEnabled(false) };
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_numeric_property() {
+        for property in [
+            NumericProperty::CurrentAllocatedBytes,
+            NumericProperty::HeapSize,
+            NumericProperty::VirtualMemoryUsed,
+            NumericProperty::PhysicalMemoryUsed,
+            NumericProperty::PeakMemoryUsage,
+            NumericProperty::RealizedFragmentation,
+            NumericProperty::MaxTotalThreadCacheBytes,
+            NumericProperty::CurrentTotalThreadCacheBytes,
+            NumericProperty::ThreadCacheCount,
+            NumericProperty::PageheapFreeBytes,
+            NumericProperty::PageheapUnmappedBytes,
+            NumericProperty::SlackBytes,
+            NumericProperty::CpuFree,
+            NumericProperty::CentralCacheFree,
+            NumericProperty::TransferCacheFree,
+            NumericProperty::ShardedTransferCacheFree,
+            NumericProperty::MetadataBytes,
+            NumericProperty::LocalBytes,
+            NumericProperty::ExternalFragmentationBytes,
+            NumericProperty::SampledInternalFragmentation,
+            NumericProperty::RequiredBytes,
+            NumericProperty::PerCpuCachesActive,
+            NumericProperty::DesiredUsageLimitBytes,
+            NumericProperty::HardUsageLimitBytes,
+            NumericProperty::SoftLimitHits,
+            NumericProperty::HardLimitHits,
+            NumericProperty::SuccessfulShrinksAfterSoftLimitHit,
+            NumericProperty::SuccessfulShrinksAfterHardLimitHit,
+            NumericProperty::NumReleasedTotalBytes,
+            NumericProperty::NumReleasedReleaseMemoryToSystemBytes,
+            NumericProperty::NumReleasedProcessBackgroundActionsBytes,
+            NumericProperty::NumReleasedSoftLimitExceededBytes,
+            NumericProperty::NumReleasedHardLimitExceededBytes,
+        ] {
+            assert!(
+                TCMalloc::numeric_property(property).is_some(),
+                "{}",
+                property.name()
+            );
+        }
+        assert_eq!(TCMalloc::numeric_property("generic.unknown_property"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stats_text() {
+        let stats = TCMalloc::stats_text();
+        assert!(stats.contains("MALLOC:"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_properties() {
+        let properties = TCMalloc::properties();
+        assert!(properties.contains_key("generic.physical_memory_used"));
+        assert!(properties.contains_key("tcmalloc.page_heap_free"));
+    }
+
+    #[test]
+    fn test_ownership() {
+        use core::alloc::GlobalAlloc;
+
+        let layout = Layout::from_size_align(100, 8).unwrap();
+        unsafe {
+            let ptr = TCMalloc.alloc(layout);
+            assert_eq!(TCMalloc::ownership(ptr), Ownership::Owned);
+            assert!(TCMalloc::allocated_size(ptr).is_some_and(|size| size >= 100));
+            TCMalloc.dealloc(ptr, layout);
+        }
+
+        let local = 0u8;
+        assert_eq!(TCMalloc::ownership(&local), Ownership::NotOwned);
+        assert_eq!(unsafe { TCMalloc::allocated_size(&local) }, None);
+        assert_eq!(TCMalloc::ownership(core::ptr::null()), Ownership::NotOwned);
+        assert_eq!(unsafe { TCMalloc::allocated_size(core::ptr::null()) }, None);
+    }
+
+    #[test]
+    fn test_good_size() {
+        use core::alloc::GlobalAlloc;
+
+        for (size, align) in [(1, 1), (100, 8), (100, 64), (5000, 4096), (1 << 20, 16)] {
+            let layout = Layout::from_size_align(size, align).unwrap();
+            let good_size = TCMalloc::good_size(layout);
+            assert!(good_size >= size);
+            assert_eq!(
+                TCMalloc::good_size(Layout::from_size_align(good_size, align).unwrap()),
+                good_size
+            );
+            unsafe {
+                let ptr = TCMalloc.alloc(layout);
+                assert!(TCMalloc::allocated_size(ptr).is_some_and(|allocated| allocated >= size));
+                TCMalloc.dealloc(ptr, layout);
+            }
+        }
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        // Setting the hard limit affects allocations of the tests running in parallel and is
+        // logged by tcmalloc, so only the soft limit is changed.
+        assert_eq!(TCMalloc::memory_limit(LimitKind::Hard), usize::MAX);
+        let limit = TCMalloc::memory_limit(LimitKind::Soft);
+        TCMalloc::set_memory_limit(1 << 40, LimitKind::Soft);
+        assert_eq!(TCMalloc::memory_limit(LimitKind::Soft), 1 << 40);
+        TCMalloc::set_memory_limit(0, LimitKind::Soft);
+        assert_eq!(TCMalloc::memory_limit(LimitKind::Soft), usize::MAX);
+        TCMalloc::set_memory_limit(limit, LimitKind::Soft);
+    }
+
+    #[test]
+    fn test_release_memory() {
+        use core::alloc::GlobalAlloc;
+
+        let layout = Layout::from_size_align(64 << 20, 8).unwrap();
+        unsafe {
+            let ptr = TCMalloc.alloc(layout);
+            ptr.write_bytes(0xff, layout.size());
+            TCMalloc.dealloc(ptr, layout);
+        }
+        assert!(TCMalloc::release_memory_to_system(1 << 30) <= 1 << 30);
+        TCMalloc::release_cpu_memory(0);
+        assert_eq!(TCMalloc::release_cpu_memory(-1), 0);
+        assert_eq!(TCMalloc::release_cpu_memory(i32::MAX), 0);
+    }
+
+    #[test]
+    fn test_idle_guard() {
+        use core::alloc::GlobalAlloc;
+
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let ptr = unsafe { TCMalloc.alloc(layout) };
+        let idle = TCMalloc::idle_guard();
+        unsafe { TCMalloc.dealloc(ptr, layout) };
+        drop(idle);
+        let ptr = unsafe { TCMalloc.alloc(layout) };
+        assert!(!ptr.is_null());
+        unsafe { TCMalloc.dealloc(ptr, layout) };
+    }
+
+    #[test]
+    fn test_background_settings() {
+        let interval = TCMalloc::background_process_sleep_interval();
+        assert!(!interval.is_zero());
+        TCMalloc::set_background_process_sleep_interval(Duration::from_millis(100));
+        assert_eq!(
+            TCMalloc::background_process_sleep_interval(),
+            Duration::from_millis(100)
+        );
+        TCMalloc::set_background_process_sleep_interval(Duration::ZERO);
+        assert_eq!(
+            TCMalloc::background_process_sleep_interval(),
+            TCMalloc::MIN_BACKGROUND_PROCESS_SLEEP_INTERVAL
+        );
+        TCMalloc::set_background_process_sleep_interval(interval);
+
+        let rate = TCMalloc::background_release_rate();
+        TCMalloc::set_background_release_rate(BytesPerSecond(1 << 20));
+        assert_eq!(TCMalloc::background_release_rate(), BytesPerSecond(1 << 20));
+        TCMalloc::set_background_release_rate(rate);
     }
 }
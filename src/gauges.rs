@@ -0,0 +1,40 @@
+use crate::NumericProperty;
+
+/// Exported gauges: metric name, help text and the source property.
+pub(crate) const GAUGES: [(&str, &str, NumericProperty); 7] = [
+    (
+        "tcmalloc_heap_size_bytes",
+        "Bytes in the heap, including fragmentation and free memory which is not released to OS.",
+        NumericProperty::HeapSize,
+    ),
+    (
+        "tcmalloc_allocated_bytes",
+        "Bytes currently allocated by application.",
+        NumericProperty::CurrentAllocatedBytes,
+    ),
+    (
+        "tcmalloc_page_heap_free_bytes",
+        "Bytes in free, mapped pages in the page heap.",
+        NumericProperty::PageheapFreeBytes,
+    ),
+    (
+        "tcmalloc_page_heap_unmapped_bytes",
+        "Bytes in free, unmapped pages in the page heap.",
+        NumericProperty::PageheapUnmappedBytes,
+    ),
+    (
+        "tcmalloc_per_cpu_cache_free_bytes",
+        "Bytes in per-CPU caches.",
+        NumericProperty::CpuFree,
+    ),
+    (
+        "tcmalloc_soft_limit_bytes",
+        "Soft memory limit in bytes.",
+        NumericProperty::DesiredUsageLimitBytes,
+    ),
+    (
+        "tcmalloc_hard_limit_bytes",
+        "Hard memory limit in bytes.",
+        NumericProperty::HardUsageLimitBytes,
+    ),
+];
@@ -1,5 +1,6 @@
 #![no_std]
 #![cfg_attr(docsrs, feature(doc_cfg))]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 
 //! A drop-in global allocator wrapper around the [TCMalloc](https://github.com/google/tcmalloc) allocator.
 //! TCMalloc is a general-purpose, performance-oriented allocator built by Google.
@@ -32,69 +33,224 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "extension")))]
 mod extension;
 
-use core::alloc::{GlobalAlloc, Layout};
+#[cfg(feature = "extension")]
+#[cfg_attr(docsrs, doc(cfg(feature = "extension")))]
+pub use extension::{BytesPerSecond, IdleGuard, LimitKind, NumericProperty, Ownership};
+
+#[cfg(feature = "extension")]
+#[cfg_attr(docsrs, doc(cfg(feature = "extension")))]
+pub mod stats;
+
+#[cfg(all(feature = "std", feature = "extension"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "extension"))))]
+mod background;
+
+#[cfg(all(feature = "std", feature = "extension"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "extension"))))]
+pub use background::BackgroundActions;
+
+#[cfg(all(feature = "std", feature = "extension"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "extension"))))]
+pub use background::BackgroundActionsBuilder;
+
+#[cfg(all(feature = "std", feature = "extension", target_os = "linux"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "std", feature = "extension", target_os = "linux")))
+)]
+pub use background::Priority;
+
+#[cfg(all(feature = "std", feature = "extension"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "extension"))))]
+mod cgroup;
+
+#[cfg(any(feature = "prometheus", feature = "metrics"))]
+mod gauges;
+
+#[cfg(feature = "prometheus")]
+#[cfg_attr(docsrs, doc(cfg(feature = "prometheus")))]
+mod prometheus;
+
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+mod metrics;
+
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+pub use metrics::MetricsThread;
+
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub mod tokio;
+
+#[cfg(feature = "allocator_api")]
+#[cfg_attr(docsrs, doc(cfg(feature = "allocator_api")))]
+mod allocator_api;
+
+#[cfg(feature = "allocator_api2")]
+#[cfg_attr(docsrs, doc(cfg(feature = "allocator_api2")))]
+mod allocator_api2;
+
+mod cold;
+
+pub use cold::ColdAlloc;
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ptr::NonNull,
+};
 
 /// A memory allocator that can be registered as the standard library’s default
 /// through the `#[global_allocator]` attribute.
 pub struct TCMalloc;
 
-unsafe impl GlobalAlloc for TCMalloc {
+impl TCMalloc {
+    /// Allocates memory for the `layout` and returns it together with its capacity, which is
+    /// the size of the whole size class and may be larger than `layout.size()`.
+    ///
+    /// Returns `None` if out of memory.
+    ///
+    /// The memory may be freed by [`GlobalAlloc::dealloc`] with the same alignment and any size
+    /// between `layout.size()` and the returned capacity.
     #[inline]
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    pub fn alloc_with_size(layout: Layout) -> Option<(NonNull<u8>, usize)> {
         let (size, alignment) = (layout.size(), layout.align());
-        let ptr =
-            unsafe { libtcmalloc_sys::BridgeTCMallocInternalNewAlignedNothrow(size, alignment) };
-        ptr as *mut u8
+        let libtcmalloc_sys::SizedPtr { p, n } = unsafe {
+            libtcmalloc_sys::tcmalloc_size_returning_operator_new_aligned_nothrow(size, alignment)
+        };
+        NonNull::new(p as *mut u8).map(|ptr| (ptr, n))
     }
 
-    #[cfg(not(feature = "realloc"))]
+    /// Same as [`TCMalloc::alloc_with_size`], but with the access frequency `hint`:
+    /// 0 for rarely accessed memory up to 255 for very frequently accessed memory.
+    ///
+    /// Rarely accessed allocations are placed into separate cold pages, so they do not occupy
+    /// hugepages of frequently accessed memory.
     #[inline]
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    pub fn alloc_hinted(layout: Layout, hint: u8) -> Option<(NonNull<u8>, usize)> {
+        let (size, alignment) = (layout.size(), layout.align());
+        let libtcmalloc_sys::SizedPtr { p, n } = unsafe {
+            libtcmalloc_sys::tcmalloc_size_returning_operator_new_aligned_hot_cold_nothrow(
+                size, alignment, hint,
+            )
+        };
+        NonNull::new(p as *mut u8).map(|ptr| (ptr, n))
+    }
+
+    /// Tries to resize the allocation at `ptr` to `new_size` bytes without moving it and returns
+    /// true on success. Never allocates, so the caller may fall back to its own copying strategy.
+    ///
+    /// Allocations of the same size class and page-level allocations of the same number of pages
+    /// are resized in place, except sampled allocations and growth which would be sampled.
+    /// The sampler and the malloc hooks are notified of the new size as by `realloc`. After
+    /// a successful resize, the memory must be freed with the layout of `new_size` and
+    /// `layout.align()`.
+    ///
+    /// # Safety
+    ///
+    /// Same as for [`GlobalAlloc::realloc`]: `ptr` must be currently allocated by TCMalloc with
+    /// the `layout`, `new_size` must be greater than zero and not overflow `isize` when rounded
+    /// up to `layout.align()`.
+    #[inline]
+    pub unsafe fn resize_in_place(ptr: *mut u8, layout: Layout, new_size: usize) -> bool {
         let ptr = ptr as *mut core::ffi::c_void;
+        let size_class = unsafe { libtcmalloc_sys::BridgeGetSizeClassOf(ptr) };
+        let new_size_class =
+            unsafe { libtcmalloc_sys::BridgeGetSizeClassFor(ptr, new_size, layout.align()) };
+        let fits = if size_class != 0 || new_size_class != 0 {
+            size_class == new_size_class
+        } else {
+            match unsafe { libtcmalloc_sys::BridgeGetResizablePagesSize(ptr) } {
+                0 => false,
+                pages_size => {
+                    pages_size == unsafe { libtcmalloc_sys::BridgeRoundUpToPages(new_size) }
+                }
+            }
+        };
+        fits && unsafe { libtcmalloc_sys::BridgeCompleteResizeInPlace(ptr, new_size) }
+    }
+
+    /// Allocates up to `out.len()` objects of the `layout` into `out` and returns the number of
+    /// allocated objects, which is less than `out.len()` only if out of memory.
+    ///
+    /// Objects of small size classes are taken from the transfer cache in batches instead of one
+    /// by one. Such objects are not sampled by the heap profiler.
+    ///
+    /// The objects may be freed by [`TCMalloc::free_batch`] or [`GlobalAlloc::dealloc`] with the
+    /// same `layout`.
+    #[inline]
+    pub fn alloc_batch(layout: Layout, out: &mut [*mut u8]) -> usize {
         let (size, alignment) = (layout.size(), layout.align());
         unsafe {
-            libtcmalloc_sys::TCMallocInternalDeleteSizedAligned(ptr, size, alignment);
+            libtcmalloc_sys::BridgeAllocBatch(
+                size,
+                alignment,
+                out.as_mut_ptr() as *mut *mut core::ffi::c_void,
+                out.len(),
+            )
         }
     }
 
-    #[cfg(feature = "realloc")]
+    /// Frees all objects in `ptrs` of the `layout`.
+    ///
+    /// Objects of small size classes are returned to the transfer cache in batches instead of
+    /// one by one.
+    ///
+    /// # Safety
+    ///
+    /// Every pointer in `ptrs` must be currently allocated by TCMalloc with the `layout`, e.g. by
+    /// [`TCMalloc::alloc_batch`], and must not be repeated.
+    #[inline]
+    pub unsafe fn free_batch(ptrs: &[*mut u8], layout: Layout) {
+        let (size, alignment) = (layout.size(), layout.align());
+        unsafe {
+            libtcmalloc_sys::BridgeFreeBatch(
+                ptrs.as_ptr() as *const *mut core::ffi::c_void,
+                ptrs.len(),
+                size,
+                alignment,
+            );
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for TCMalloc {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let (size, alignment) = (layout.size(), layout.align());
+        let ptr =
+            unsafe { libtcmalloc_sys::BridgeTCMallocInternalNewAlignedNothrow(size, alignment) };
+        ptr as *mut u8
+    }
+
+    // `alloc_zeroed` is not overridden: tcmalloc's calloc (`TCMallocInternalCalloc`) always
+    // clears the memory with `memset`, as tcmalloc does not track whether pages are already
+    // zeroed, so it would not be faster than the default alloc and `write_bytes`.
+
     #[inline]
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
         let ptr = ptr as *mut core::ffi::c_void;
-        let alignment = layout.align();
+        let (size, alignment) = (layout.size(), layout.align());
         unsafe {
-            libtcmalloc_sys::TCMallocInternalDeleteAligned(ptr, alignment);
+            libtcmalloc_sys::TCMallocInternalDeleteSizedAligned(ptr, size, alignment);
         }
     }
 
     #[cfg(feature = "realloc")]
     unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
-        let alignment = layout.align();
-        let ptr = ptr as *mut core::ffi::c_void;
-        let mut old_size_to_free = Default::default();
-        let new_ptr = {
-            let old_size_to_free = &mut old_size_to_free;
-            unsafe {
-                libtcmalloc_sys::BridgePrepareReallocAligned(
-                    ptr,
-                    new_size,
-                    alignment,
-                    old_size_to_free,
-                )
-            }
-        };
-        if !new_ptr.is_null() && new_ptr != ptr {
+        if unsafe { Self::resize_in_place(ptr, layout, new_size) } {
+            return ptr;
+        }
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        let new_ptr = unsafe { self.alloc(new_layout) };
+        if !new_ptr.is_null() {
             let size_to_copy = layout.size().min(new_size);
-            unsafe { core::ptr::copy_nonoverlapping(ptr, new_ptr, size_to_copy) };
             unsafe {
-                libtcmalloc_sys::TCMallocInternalDeleteSizedAligned(
-                    ptr,
-                    old_size_to_free,
-                    alignment,
-                )
-            };
+                core::ptr::copy_nonoverlapping(ptr, new_ptr, size_to_copy);
+                self.dealloc(ptr, layout);
+            }
         }
-        new_ptr as *mut u8
+        new_ptr
     }
 }
 
@@ -173,4 +329,123 @@ mod tests {
             alloc.dealloc(ptr, new_layout);
         }
     }
+
+    #[test]
+    fn it_keeps_contents_of_shrunk_memory() {
+        unsafe {
+            let alloc = TCMalloc;
+
+            for (size, new_size) in [
+                (1000, 900),
+                ((1 << 20) + 8000, (1 << 20) + 100),
+                (2 << 20, 8),
+            ] {
+                let layout = Layout::from_size_align(size, 16).unwrap();
+                let new_layout = Layout::from_size_align(new_size, layout.align()).unwrap();
+
+                let ptr = alloc.alloc(layout);
+                for i in 0..size {
+                    ptr.add(i).write(i as u8);
+                }
+                let ptr = alloc.realloc(ptr, layout, new_size);
+                assert!((0..new_size).all(|i| ptr.add(i).read() == i as u8));
+                alloc.dealloc(ptr, new_layout);
+            }
+        }
+    }
+
+    #[test]
+    fn it_resizes_memory_in_place() {
+        // Sampled allocations are never resized in place, so try a few of them. With
+        // `small_but_slow`, most of 1 MiB allocations are sampled.
+        fn resizes_in_place(layout: Layout, new_size: usize, other_size: usize) -> bool {
+            (0..64).any(|_| unsafe {
+                let alloc = TCMalloc;
+
+                let ptr = alloc.alloc(layout);
+                assert!(!TCMalloc::resize_in_place(ptr, layout, other_size));
+                if TCMalloc::resize_in_place(ptr, layout, new_size) {
+                    alloc.dealloc(
+                        ptr,
+                        Layout::from_size_align(new_size, layout.align()).unwrap(),
+                    );
+                    true
+                } else {
+                    alloc.dealloc(ptr, layout);
+                    false
+                }
+            })
+        }
+
+        assert!(resizes_in_place(
+            Layout::from_size_align(1000, 16).unwrap(),
+            900,
+            1 << 20
+        ));
+        // Both sizes span the same number of pages with any page size.
+        let page_size = unsafe { libtcmalloc_sys::BridgeRoundUpToPages(1) };
+        assert!(resizes_in_place(
+            Layout::from_size_align((1 << 20) + page_size / 2, 32).unwrap(),
+            (1 << 20) + page_size / 4,
+            1 << 20
+        ));
+    }
+
+    #[test]
+    fn it_frees_memory_allocated_in_batch() {
+        unsafe {
+            let alloc = TCMalloc;
+
+            for (layout, count) in [
+                (Layout::from_size_align(64, 16).unwrap(), 300),
+                (Layout::from_size_align(1 << 20, 32).unwrap(), 3),
+            ] {
+                let mut ptrs = [core::ptr::null_mut(); 300];
+                let ptrs = &mut ptrs[..count];
+                assert_eq!(TCMalloc::alloc_batch(layout, ptrs), count);
+                for &ptr in ptrs.iter() {
+                    assert!(!ptr.is_null());
+                    assert_eq!(ptr as usize % layout.align(), 0);
+                    ptr.write_bytes(0xff, layout.size());
+                }
+                alloc.dealloc(ptrs[0], layout);
+                TCMalloc::free_batch(&ptrs[1..], layout);
+            }
+        }
+    }
+
+    #[test]
+    fn it_frees_memory_allocated_with_size() {
+        unsafe {
+            let layout = Layout::from_size_align(100, 16).unwrap();
+            let alloc = TCMalloc;
+
+            let (ptr, capacity) = TCMalloc::alloc_with_size(layout).unwrap();
+            assert!(capacity >= layout.size());
+            assert_eq!(ptr.as_ptr() as usize % layout.align(), 0);
+            ptr.as_ptr().write_bytes(0, capacity);
+            alloc.dealloc(
+                ptr.as_ptr(),
+                Layout::from_size_align(capacity, layout.align()).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn it_frees_hinted_memory() {
+        unsafe {
+            let alloc = TCMalloc;
+
+            for (layout, hint) in [
+                (Layout::from_size_align(100, 16).unwrap(), 0),
+                (Layout::from_size_align(100, 16).unwrap(), 255),
+                (Layout::from_size_align(1 << 20, 32).unwrap(), 0),
+            ] {
+                let (ptr, capacity) = TCMalloc::alloc_hinted(layout, hint).unwrap();
+                assert!(capacity >= layout.size());
+                assert_eq!(ptr.as_ptr() as usize % layout.align(), 0);
+                alloc.dealloc(ptr.as_ptr(), layout);
+            }
+        }
+    }
 }
@@ -0,0 +1,171 @@
+extern crate std;
+
+use crate::{TCMalloc, gauges::GAUGES};
+use ::metrics::{Unit, describe_gauge, gauge};
+use core::time::Duration;
+use std::{
+    sync::{
+        Once,
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+};
+
+impl TCMalloc {
+    /// Updates allocator gauges in the [`metrics`](https://docs.rs/metrics) facade.
+    ///
+    /// Updated gauges: heap size, allocated bytes, free and unmapped page heap bytes, per-CPU cache
+    /// bytes, soft and hard memory limits. Gauges are described on the first call. Gauges which are
+    /// not reported by TCMalloc are skipped, memory limits which are not set are recorded as
+    /// infinity.
+    pub fn record_metrics() {
+        static DESCRIBE: Once = Once::new();
+        DESCRIBE.call_once(|| {
+            for (name, help, _) in GAUGES {
+                describe_gauge!(name, Unit::Bytes, help);
+            }
+        });
+
+        for (name, _, property) in GAUGES {
+            if let Some(value) = TCMalloc::numeric_property(property) {
+                gauge!(name).set(if value == usize::MAX {
+                    f64::INFINITY
+                } else {
+                    value as f64
+                });
+            }
+        }
+    }
+
+    /// Updates allocator gauges every `interval` in the background thread.
+    ///
+    /// [`process_background_actions`](TCMalloc::process_background_actions) does not return on
+    /// platforms which need it, so the gauges are updated by the separate thread. The thread is
+    /// stopped and joined when the returned handle is dropped or [`MetricsThread::stop`] is
+    /// called. See [`record_metrics`](TCMalloc::record_metrics) for the list of gauges.
+    pub fn record_metrics_thread(interval: Duration) -> MetricsThread {
+        let (stop_sender, stop) = mpsc::channel::<()>();
+        let thread = thread::Builder::new()
+            .name("tcmalloc-metrics".into())
+            .spawn(move || {
+                loop {
+                    Self::record_metrics();
+                    if stop.recv_timeout(interval) != Err(RecvTimeoutError::Timeout) {
+                        break;
+                    }
+                }
+            })
+            .expect("failed to spawn thread");
+        MetricsThread {
+            stop: Some(stop_sender),
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Handle of the thread started by [`TCMalloc::record_metrics_thread`].
+///
+/// The thread is stopped and joined when the handle is dropped or [`MetricsThread::stop`] is
+/// called.
+#[derive(Debug)]
+#[must_use = "the thread is stopped when the handle is dropped"]
+pub struct MetricsThread {
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl MetricsThread {
+    /// Stops the thread without waiting for the next update and joins it.
+    pub fn stop(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        // The thread wakes up when the channel is disconnected.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MetricsThread {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::metrics::{
+        Counter, Gauge, GaugeFn, Histogram, Key, KeyName, Metadata, Recorder, SharedString,
+    };
+    use std::{
+        string::{String, ToString},
+        sync::{Arc, Mutex},
+        vec::Vec,
+    };
+
+    #[derive(Default)]
+    struct Gauges(Mutex<Vec<(String, f64)>>);
+
+    struct RecordedGauge(Arc<Gauges>, String);
+
+    impl GaugeFn for RecordedGauge {
+        fn increment(&self, _value: f64) {}
+
+        fn decrement(&self, _value: f64) {}
+
+        fn set(&self, value: f64) {
+            self.0.0.lock().unwrap().push((self.1.clone(), value));
+        }
+    }
+
+    struct TestRecorder(Arc<Gauges>);
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, _: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::noop()
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(Arc::new(RecordedGauge(
+                self.0.clone(),
+                key.name().to_string(),
+            )))
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn test_record_metrics() {
+        let gauges = Arc::new(Gauges::default());
+        ::metrics::with_local_recorder(&TestRecorder(gauges.clone()), TCMalloc::record_metrics);
+        let gauges = gauges.0.lock().unwrap();
+        for (name, _, _) in GAUGES {
+            assert!(gauges.iter().any(|(gauge, _)| gauge == name));
+        }
+        assert!(
+            gauges
+                .iter()
+                .all(|(_, value)| *value >= 0. && !value.is_nan())
+        );
+    }
+
+    #[test]
+    fn test_record_metrics_thread() {
+        let start = std::time::Instant::now();
+        TCMalloc::record_metrics_thread(Duration::from_secs(3600)).stop();
+        assert!(start.elapsed() < Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{TCMalloc, gauges::GAUGES};
+use core::fmt;
+
+impl TCMalloc {
+    /// Writes allocator gauges in the
+    /// [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format).
+    ///
+    /// Emitted gauges: heap size, allocated bytes, free and unmapped page heap bytes, per-CPU cache
+    /// bytes, soft and hard memory limits. Gauges which are not reported by TCMalloc are skipped,
+    /// memory limits which are not set are written as `+Inf`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use tcmalloc_better::TCMalloc;
+    ///
+    /// #[global_allocator]
+    /// static GLOBAL: TCMalloc = TCMalloc;
+    ///
+    /// let mut metrics = String::new();
+    /// TCMalloc::render_prometheus(&mut metrics).unwrap();
+    /// assert!(metrics.contains("# TYPE tcmalloc_heap_size_bytes gauge"));
+    /// ```
+    pub fn render_prometheus(out: &mut impl fmt::Write) -> fmt::Result {
+        for (name, help, property) in GAUGES {
+            let Some(value) = TCMalloc::numeric_property(property) else {
+                continue;
+            };
+            writeln!(out, "# HELP {name} {help}")?;
+            writeln!(out, "# TYPE {name} gauge")?;
+            if value == usize::MAX {
+                writeln!(out, "{name} +Inf")?;
+            } else {
+                writeln!(out, "{name} {value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::string::String;
+
+    #[test]
+    fn test_render_prometheus() {
+        let mut metrics = String::new();
+        TCMalloc::render_prometheus(&mut metrics).unwrap();
+        for (name, _, _) in GAUGES {
+            assert!(metrics.contains(&std::format!("# TYPE {name} gauge\n{name} ")));
+        }
+        assert!(metrics.ends_with('\n'));
+    }
+}
@@ -0,0 +1,167 @@
+//! Typed snapshots of the allocator statistics.
+
+use crate::{NumericProperty, TCMalloc};
+
+#[cfg(feature = "std")]
+mod report;
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use report::{
+    FillerHistogram, FillerStats, HistogramBucket, Report, SizeClassStats, TransferCacheStats,
+    parse_report,
+};
+
+/// Snapshot of the allocator byte counts, grouped by the allocator component.
+///
+/// Statistics which are not reported by TCMalloc are set to zero.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct AllocatorStats {
+    /// Heap totals.
+    pub heap: HeapBytes,
+    /// Page heap free memory.
+    pub page_heap: PageHeapBytes,
+    /// Per-CPU caches.
+    pub per_cpu_cache: PerCpuCacheBytes,
+    /// Per-thread caches. Used only with `deprecated_perthread` feature or when per-CPU caches
+    /// are not available.
+    pub thread_cache: ThreadCacheBytes,
+    /// Cross-thread transfer caches.
+    pub transfer_cache: TransferCacheBytes,
+    /// Central free lists.
+    pub central_freelist: CentralFreelistBytes,
+    /// Bytes used by internal data structures.
+    pub metadata_bytes: usize,
+}
+
+/// Heap totals.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct HeapBytes {
+    /// Bytes currently allocated by application.
+    pub allocated: usize,
+    /// Bytes in the heap: allocated bytes + fragmentation + freed (but not released to OS)
+    /// memory regions.
+    pub heap_size: usize,
+    /// Overall physical memory used, including malloc internals.
+    pub physical_memory_used: usize,
+    /// Overall virtual memory used, including malloc internals.
+    pub virtual_memory_used: usize,
+}
+
+/// Page heap free memory.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct PageHeapBytes {
+    /// Bytes in free, mapped pages.
+    pub free: usize,
+    /// Bytes in free, unmapped pages (released to OS).
+    pub unmapped: usize,
+}
+
+/// Per-CPU caches.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct PerCpuCacheBytes {
+    /// Whether TCMalloc is using per-CPU caches.
+    pub active: bool,
+    /// Bytes in per-CPU free lists.
+    pub free: usize,
+}
+
+/// Per-thread caches.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ThreadCacheBytes {
+    /// Bytes in per-thread free lists.
+    pub free: usize,
+    /// Number of thread caches in use.
+    pub count: usize,
+}
+
+/// Cross-thread transfer caches.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct TransferCacheBytes {
+    /// Bytes in transfer caches.
+    pub free: usize,
+    /// Bytes in sharded transfer caches.
+    pub sharded_free: usize,
+}
+
+/// Central free lists.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct CentralFreelistBytes {
+    /// Bytes in central free lists.
+    pub free: usize,
+}
+
+impl AllocatorStats {
+    /// Takes a snapshot of the current allocator statistics.
+    ///
+    /// Statistics are gathered one by one, so the snapshot is not atomic with respect to
+    /// concurrent allocations.
+    pub fn snapshot() -> Self {
+        let property = |property| TCMalloc::numeric_property(property).unwrap_or_default();
+        Self {
+            heap: HeapBytes {
+                allocated: property(NumericProperty::CurrentAllocatedBytes),
+                heap_size: property(NumericProperty::HeapSize),
+                physical_memory_used: property(NumericProperty::PhysicalMemoryUsed),
+                virtual_memory_used: property(NumericProperty::VirtualMemoryUsed),
+            },
+            page_heap: PageHeapBytes {
+                free: property(NumericProperty::PageheapFreeBytes),
+                unmapped: property(NumericProperty::PageheapUnmappedBytes),
+            },
+            per_cpu_cache: PerCpuCacheBytes {
+                active: property(NumericProperty::PerCpuCachesActive) != 0,
+                free: property(NumericProperty::CpuFree),
+            },
+            thread_cache: ThreadCacheBytes {
+                free: property(NumericProperty::CurrentTotalThreadCacheBytes),
+                count: property(NumericProperty::ThreadCacheCount),
+            },
+            transfer_cache: TransferCacheBytes {
+                free: property(NumericProperty::TransferCacheFree),
+                sharded_free: property(NumericProperty::ShardedTransferCacheFree),
+            },
+            central_freelist: CentralFreelistBytes {
+                free: property(NumericProperty::CentralCacheFree),
+            },
+            metadata_bytes: property(NumericProperty::MetadataBytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let stats = AllocatorStats::snapshot();
+        assert!(stats.heap.virtual_memory_used >= stats.heap.physical_memory_used);
+        assert_ne!(stats.metadata_bytes, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        let stats = AllocatorStats::snapshot();
+        let json = serde_json::to_string(&stats).unwrap();
+        assert_eq!(
+            serde_json::from_str::<AllocatorStats>(&json).unwrap(),
+            stats
+        );
+    }
+}
@@ -0,0 +1,536 @@
+extern crate std;
+
+use core::str::FromStr;
+use std::{string::String, vec::Vec};
+
+/// Label of the page allocator, whose report is not wrapped into `Begin`/`End` markers.
+const NORMAL_MEMORY_TAG: &str = "NORMAL";
+
+/// Typed view of the human-readable report returned by [`TCMalloc::stats_text`].
+///
+/// Only tables covered by this type are parsed, all other lines are ignored.
+///
+/// [`TCMalloc::stats_text`]: crate::TCMalloc::stats_text
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Report {
+    /// TCMalloc logical page size in bytes.
+    pub page_size: usize,
+    /// Hugepage size in bytes.
+    pub hugepage_size: usize,
+    /// Freelists and spans by size class.
+    pub size_classes: Vec<SizeClassStats>,
+    /// Transfer cache freelists by size class.
+    pub transfer_cache: Vec<TransferCacheStats>,
+    /// Sharded transfer cache freelists by size class.
+    pub sharded_transfer_cache: Vec<TransferCacheStats>,
+    /// HugePageFiller state of every hugepage-aware page allocator.
+    pub fillers: Vec<FillerStats>,
+}
+
+/// Freelists and spans of one size class.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct SizeClassStats {
+    /// Size class index.
+    pub size_class: u32,
+    /// Size of objects of this size class in bytes.
+    pub object_size: usize,
+    /// Number of free objects in per-thread, per-CPU, transfer and central caches.
+    pub free_objects: u64,
+    /// Number of pages held by live spans of this size class.
+    pub live_pages: u64,
+    /// Number of spans returned to the page heap.
+    pub spans_returned: u64,
+    /// Number of spans requested from the page heap.
+    pub spans_requested: u64,
+}
+
+impl SizeClassStats {
+    /// Bytes in free objects of this size class.
+    #[inline]
+    pub fn free_bytes(&self) -> u64 {
+        self.free_objects * self.object_size as u64
+    }
+
+    /// Bytes held by live spans of this size class.
+    #[inline]
+    pub fn live_bytes(&self, page_size: usize) -> u64 {
+        self.live_pages * page_size as u64
+    }
+
+    /// Share of free bytes in the bytes held by live spans of this size class.
+    ///
+    /// Returns 0 if there are no live spans.
+    pub fn fragmentation(&self, page_size: usize) -> f64 {
+        match self.live_bytes(page_size) {
+            0 => 0.,
+            live_bytes => self.free_bytes() as f64 / live_bytes as f64,
+        }
+    }
+}
+
+/// Transfer cache freelist of one size class.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct TransferCacheStats {
+    /// Size class index.
+    pub size_class: u32,
+    /// Size of objects of this size class in bytes.
+    pub object_size: usize,
+    /// Number of objects in the cache.
+    pub objects: u64,
+    /// Current capacity in objects.
+    pub capacity: u64,
+    /// Maximum allowed capacity in objects.
+    pub max_capacity: u64,
+    /// Number of successful batch inserts.
+    pub insert_hits: u64,
+    /// Number of failed batch inserts.
+    pub insert_misses: u64,
+    /// Number of objects in failed inserts. Not reported for sharded transfer caches.
+    pub insert_object_misses: Option<u64>,
+    /// Number of successful batch removes.
+    pub remove_hits: u64,
+    /// Number of failed batch removes.
+    pub remove_misses: u64,
+    /// Number of objects in failed removes. Not reported for sharded transfer caches.
+    pub remove_object_misses: Option<u64>,
+}
+
+/// HugePageFiller state of one page allocator.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct FillerStats {
+    /// Memory tag of the page allocator, e.g. `NORMAL`, `SAMPLED` or `COLD`.
+    pub memory_tag: String,
+    /// Number of hugepages in the filler.
+    pub total_hugepages: u64,
+    /// Number of full hugepages.
+    pub full_hugepages: u64,
+    /// Number of partially used hugepages.
+    pub partial_hugepages: u64,
+    /// Number of hugepages with released pages.
+    pub released_hugepages: u64,
+    /// Number of partially used hugepages with released pages.
+    pub partially_released_hugepages: u64,
+    /// Number of quarantined hugepages.
+    pub quarantined_hugepages: u64,
+    /// Number of free pages in all hugepages.
+    pub free_pages: u64,
+    /// Share of free pages in all hugepages.
+    pub free_ratio: f64,
+    /// Share of free pages in not full hugepages.
+    pub non_full_free_ratio: f64,
+    /// Hugepage histograms by free pages, longest free range, allocations and lifetime.
+    pub histograms: Vec<FillerHistogram>,
+}
+
+/// One of HugePageFiller histograms.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct FillerHistogram {
+    /// Subset of hugepages the histogram is limited to,
+    /// e.g. `hps with >= 224 free pages, with different lifetimes`.
+    pub group: Option<String>,
+    /// Histogram title, e.g. `sparsely-accessed regular hps with a<= # of free pages <b`.
+    pub title: String,
+    /// Buckets in ascending order of their lower bounds.
+    pub buckets: Vec<HistogramBucket>,
+}
+
+/// Bucket of [`FillerHistogram`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct HistogramBucket {
+    /// Inclusive lower bound of the bucket. Lifetime histograms use milliseconds.
+    pub lower_bound: u64,
+    /// Number of hugepages in the bucket.
+    pub count: u64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Section {
+    SizeClasses,
+    TransferCache,
+    ShardedTransferCache,
+    Other,
+}
+
+/// Parses the human-readable report returned by [`TCMalloc::stats_text`].
+///
+/// Lines which are not recognized are skipped, so reports of other TCMalloc versions are parsed
+/// on the best-effort basis.
+///
+/// [`TCMalloc::stats_text`]: crate::TCMalloc::stats_text
+pub fn parse_report(report: &str) -> Report {
+    let mut result = Report::default();
+    let mut section = Section::Other;
+    let mut after_separator = false;
+    let mut memory_tag = NORMAL_MEMORY_TAG;
+    let mut histogram_group = None;
+
+    for line in report.lines() {
+        if line.starts_with("------") {
+            after_separator = true;
+            continue;
+        }
+        let class_row = parse_class_row(line);
+        // The sharded transfer cache state is printed between the table header and its rows.
+        if after_separator
+            && class_row.is_none()
+            && !line.starts_with("Sharded transfer cache state")
+        {
+            section = if line.starts_with("Total size of freelists") {
+                Section::SizeClasses
+            } else if line.starts_with("Used bytes, current capacity") {
+                Section::TransferCache
+            } else if line.starts_with("Cumulative sharded transfer cache stats") {
+                Section::ShardedTransferCache
+            } else {
+                Section::Other
+            };
+        }
+        after_separator = false;
+
+        if let Some((size_class, object_size, fields)) = class_row {
+            match section {
+                Section::SizeClasses => {
+                    result
+                        .size_classes
+                        .extend(parse_size_class(size_class, object_size, fields))
+                }
+                Section::TransferCache => result.transfer_cache.extend(parse_transfer_cache(
+                    size_class,
+                    object_size,
+                    fields,
+                )),
+                Section::ShardedTransferCache => result
+                    .sharded_transfer_cache
+                    .extend(parse_transfer_cache(size_class, object_size, fields)),
+                Section::Other => {}
+            }
+        } else if let Some(line) = line.strip_prefix("MALLOC: ") {
+            if line.ends_with("Tcmalloc page size") {
+                result.page_size = value_before(line, " Tcmalloc").unwrap_or_default();
+            } else if line.ends_with("Tcmalloc hugepage size") {
+                result.hugepage_size = value_before(line, " Tcmalloc").unwrap_or_default();
+            }
+        } else if let Some(line) = line.strip_prefix(">>>>>>> Begin ") {
+            memory_tag = line.split_once(' ').map_or(line, |(tag, _)| tag);
+        } else if line.starts_with(">>>>>>> End ") {
+            memory_tag = NORMAL_MEMORY_TAG;
+        } else if let Some(line) = line.strip_prefix("HugePageFiller: ") {
+            parse_filler_line(line, memory_tag, &mut histogram_group, &mut result.fillers);
+        }
+    }
+
+    result
+}
+
+/// Splits `class   1 [        8 bytes ] : <fields>` into size class, object size and fields.
+fn parse_class_row(line: &str) -> Option<(u32, usize, &str)> {
+    let line = line.strip_prefix("class ")?;
+    let (size_class, line) = line.split_once('[')?;
+    let (object_size, fields) = line.split_once("bytes ]")?;
+    let fields = fields.trim_start().strip_prefix(':')?;
+    Some((
+        size_class.trim().parse().ok()?,
+        object_size.trim().parse().ok()?,
+        fields,
+    ))
+}
+
+fn parse_size_class(size_class: u32, object_size: usize, fields: &str) -> Option<SizeClassStats> {
+    // Classes which are not used by the current size class configuration are reported with
+    // the zero object size.
+    if object_size == 0 {
+        return None;
+    }
+    Some(SizeClassStats {
+        size_class,
+        object_size,
+        free_objects: field_value(fields, " objs")?,
+        live_pages: field_value(fields, " live pages")?,
+        spans_returned: field_value(fields, " ret")?,
+        spans_requested: field_value(fields, " req")?,
+    })
+}
+
+fn parse_transfer_cache(
+    size_class: u32,
+    object_size: usize,
+    fields: &str,
+) -> Option<TransferCacheStats> {
+    if object_size == 0 {
+        return None;
+    }
+    let object_misses =
+        |key| field(fields, key).and_then(|field| value_before(field, " object misses"));
+    Some(TransferCacheStats {
+        size_class,
+        object_size,
+        objects: field_value(fields, " objs")?,
+        capacity: field_value(fields, " capacity")?,
+        max_capacity: field_value(fields, " max_capacity")?,
+        insert_hits: field_value(fields, " insert hits")?,
+        insert_misses: field_value(fields, " insert misses")?,
+        insert_object_misses: object_misses(" insert misses"),
+        remove_hits: field_value(fields, " remove hits")?,
+        remove_misses: field_value(fields, " remove misses")?,
+        remove_object_misses: object_misses(" remove misses"),
+    })
+}
+
+fn parse_filler_line(
+    line: &str,
+    memory_tag: &str,
+    histogram_group: &mut Option<String>,
+    fillers: &mut Vec<FillerStats>,
+) {
+    if line.starts_with("densely pack small requests into hugepages") {
+        *histogram_group = None;
+        fillers.push(FillerStats {
+            memory_tag: memory_tag.into(),
+            ..Default::default()
+        });
+        return;
+    }
+    let Some(filler) = fillers.last_mut() else {
+        return;
+    };
+
+    if let Some(title) = line.strip_prefix("# of ") {
+        // A title which is immediately followed by another title has no buckets, it limits
+        // the following histograms to a subset of hugepages.
+        if filler
+            .histograms
+            .last()
+            .is_some_and(|histogram| histogram.buckets.is_empty())
+        {
+            *histogram_group = filler.histograms.pop().map(|histogram| histogram.title);
+        }
+        filler.histograms.push(FillerHistogram {
+            group: histogram_group.clone(),
+            title: title.trim_end_matches('.').into(),
+            buckets: Vec::new(),
+        });
+    } else if line.starts_with('<') {
+        if let Some(histogram) = filler.histograms.last_mut() {
+            histogram.buckets.extend(parse_buckets(line));
+        }
+    } else {
+        *histogram_group = None;
+        if let Some(line) = line.strip_prefix("Overall, ") {
+            filler.total_hugepages = value_before(line, " total").unwrap_or_default();
+            filler.full_hugepages = value_before(line, " full").unwrap_or_default();
+            filler.partial_hugepages = value_before(line, " partial,").unwrap_or_default();
+            filler.released_hugepages = value_before(line, " released").unwrap_or_default();
+            filler.partially_released_hugepages =
+                value_before(line, " partially)").unwrap_or_default();
+            filler.quarantined_hugepages = value_before(line, " quarantined").unwrap_or_default();
+        } else if let Some(line) = line.strip_prefix("among non-fulls, ") {
+            filler.non_full_free_ratio = value_before(line, " free").unwrap_or_default();
+        } else if line.contains(" pages free in ") {
+            filler.free_pages = value_before(line, " pages free").unwrap_or_default();
+            filler.free_ratio = value_before(line, " free").unwrap_or_default();
+        }
+    }
+}
+
+/// Parses `<  0<=    19 <  1<=     5` or `<   0 ms <=      0 <   1 ms <=      0` buckets.
+fn parse_buckets(line: &str) -> impl Iterator<Item = HistogramBucket> {
+    let mut segments = line.split("<=");
+    let mut lower_bound = segments.next();
+    segments.filter_map(move |segment| {
+        let lower_bound = lower_bound.replace(segment)?;
+        Some(HistogramBucket {
+            lower_bound: lower_bound
+                .rsplit_once('<')?
+                .1
+                .split_whitespace()
+                .next()?
+                .parse()
+                .ok()?,
+            count: segment.split_whitespace().next()?.parse().ok()?,
+        })
+    })
+}
+
+/// Finds `;`-separated field containing `key`.
+fn field<'a>(fields: &'a str, key: &str) -> Option<&'a str> {
+    fields.split(';').find(|field| field.contains(key))
+}
+
+/// Parses the number, which precedes `key` in the `;`-separated field containing `key`.
+fn field_value<T: FromStr>(fields: &str, key: &str) -> Option<T> {
+    value_before(field(fields, key)?, key)
+}
+
+/// Parses the word immediately preceding the last occurrence of `key`.
+fn value_before<T: FromStr>(text: &str, key: &str) -> Option<T> {
+    let (before, _) = text.rsplit_once(key)?;
+    before
+        .rsplit(|c: char| c.is_whitespace() || c == '(' || c == ',')
+        .find(|word| !word.is_empty())?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TCMalloc;
+
+    const REPORT_8K_PAGES: &str = include_str!("testdata/report_8k_pages.txt");
+    const REPORT_32K_PAGES: &str = include_str!("testdata/report_32k_pages.txt");
+    const REPORT_256K_PAGES: &str = include_str!("testdata/report_256k_pages.txt");
+
+    #[test]
+    fn test_parse_report() {
+        let report = parse_report(REPORT_8K_PAGES);
+        assert_eq!(report.page_size, 8 << 10);
+        assert_eq!(report.hugepage_size, 2 << 20);
+
+        let size_class = report.size_classes[0];
+        assert_eq!(size_class.size_class, 1);
+        assert_eq!(size_class.object_size, 8);
+        assert_eq!(size_class.free_objects, 999);
+        assert_eq!(size_class.live_pages, 1);
+        assert_eq!(size_class.spans_returned, 0);
+        assert_eq!(size_class.spans_requested, 1);
+        assert_eq!(size_class.free_bytes(), 7992);
+        assert_eq!(size_class.fragmentation(report.page_size), 7992. / 8192.);
+        assert!(
+            report
+                .size_classes
+                .windows(2)
+                .all(|w| w[0].size_class < w[1].size_class)
+        );
+
+        let transfer_cache = report.transfer_cache[0];
+        assert_eq!(transfer_cache.size_class, 1);
+        assert_eq!(transfer_cache.objects, 10);
+        assert_eq!(transfer_cache.capacity, 512);
+        assert_eq!(transfer_cache.max_capacity, 2048);
+        assert_eq!(transfer_cache.insert_hits, 10);
+        assert_eq!(transfer_cache.insert_misses, 0);
+        assert_eq!(transfer_cache.insert_object_misses, Some(0));
+        assert_eq!(transfer_cache.remove_hits, 0);
+        assert_eq!(transfer_cache.remove_misses, 8);
+        assert_eq!(transfer_cache.remove_object_misses, Some(36));
+        assert_eq!(report.transfer_cache.len(), report.size_classes.len());
+
+        assert_eq!(
+            report.sharded_transfer_cache.len(),
+            report.size_classes.len()
+        );
+        assert!(
+            report
+                .sharded_transfer_cache
+                .iter()
+                .all(|stats| stats.insert_object_misses.is_none())
+        );
+
+        let tags: Vec<_> = report
+            .fillers
+            .iter()
+            .map(|filler| filler.memory_tag.as_str())
+            .collect();
+        assert_eq!(tags, ["NORMAL", "SAMPLED", "COLD"]);
+        let filler = &report.fillers[0];
+        assert_eq!(filler.total_hugepages, 29);
+        assert_eq!(filler.full_hugepages, 19);
+        assert_eq!(filler.partial_hugepages, 10);
+        assert_eq!(filler.released_hugepages, 0);
+        assert_eq!(filler.free_pages, 279);
+        assert_eq!(filler.free_ratio, 0.0376);
+        assert_eq!(filler.non_full_free_ratio, 0.1090);
+
+        let histogram = &filler.histograms[0];
+        assert_eq!(histogram.group, None);
+        assert_eq!(
+            histogram.title,
+            "sparsely-accessed regular hps with a<= # of free pages <b"
+        );
+        assert_eq!(histogram.buckets.len(), 32);
+        assert_eq!(
+            histogram.buckets[..2],
+            [
+                HistogramBucket {
+                    lower_bound: 0,
+                    count: 19
+                },
+                HistogramBucket {
+                    lower_bound: 1,
+                    count: 5
+                }
+            ]
+        );
+        assert!(
+            filler
+                .histograms
+                .iter()
+                .all(|histogram| !histogram.buckets.is_empty())
+        );
+
+        let lifetime = filler
+            .histograms
+            .iter()
+            .find(|histogram| {
+                histogram.group.as_deref()
+                    == Some("hps with >= 224 free pages, with different lifetimes")
+            })
+            .unwrap();
+        assert_eq!(
+            lifetime.title,
+            "sparsely-accessed regular hps with lifetime a <= # hps < b"
+        );
+        assert_eq!(lifetime.buckets.len(), 8);
+        assert_eq!(lifetime.buckets[7].lower_bound, 1000000);
+    }
+
+    #[test]
+    fn test_parse_report_page_sizes() {
+        for (report, page_size) in [
+            (REPORT_8K_PAGES, 8 << 10),
+            (REPORT_32K_PAGES, 32 << 10),
+            (REPORT_256K_PAGES, 256 << 10),
+        ] {
+            let report = parse_report(report);
+            assert_eq!(report.page_size, page_size);
+            assert!(!report.size_classes.is_empty());
+            assert!(!report.transfer_cache.is_empty());
+            assert!(!report.fillers.is_empty());
+            assert!(report.size_classes.iter().all(|size_class| {
+                (0. ..=1.).contains(&size_class.fragmentation(report.page_size))
+            }));
+        }
+    }
+
+    #[test]
+    fn test_parse_stats_text() {
+        let report = parse_report(&TCMalloc::stats_text());
+        assert_ne!(report.page_size, 0);
+        assert!(!report.size_classes.is_empty());
+    }
+
+    #[test]
+    fn test_parse_unknown() {
+        assert_eq!(parse_report("unknown\n---\nclass 1"), Report::default());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        let report = parse_report(REPORT_8K_PAGES);
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(serde_json::from_str::<Report>(&json).unwrap(), report);
+    }
+}
@@ -0,0 +1,176 @@
+//! Integration with the [`tokio`](https://docs.rs/tokio) runtime.
+//!
+//! Marking worker threads idle is opt-in: pass [`on_thread_park`] and [`on_thread_unpark`] to
+//! the runtime builder. It returns per-thread caches of the `deprecated_perthread` feature to
+//! the central free lists and is useless with the default per-CPU caches.
+//!
+//! # Example
+//! ```rust
+//! use tcmalloc_better::{TCMalloc, tokio};
+//!
+//! #[global_allocator]
+//! static GLOBAL: TCMalloc = TCMalloc;
+//!
+//! let runtime = ::tokio::runtime::Builder::new_multi_thread()
+//!     .on_thread_park(tokio::on_thread_park)
+//!     .on_thread_unpark(tokio::on_thread_unpark)
+//!     .build()
+//!     .unwrap();
+//! tokio::spawn_background_actions(runtime.handle()).unwrap();
+//!
+//! // Rest of main
+//!
+//! // Background actions are stopped on the runtime shutdown.
+//! drop(runtime);
+//! ```
+
+extern crate std;
+
+use crate::{
+    TCMalloc,
+    background::{self, Running},
+};
+use ::tokio::{runtime::Handle, task::JoinHandle};
+use core::{cell::Cell, future, time::Duration};
+use libtcmalloc_sys::BridgeSetBackgroundProcessActionsEnabled;
+use std::{io, time::Instant};
+
+/// The shortest time between marking the same worker thread idle.
+const IDLE_INTERVAL: Duration = Duration::from_secs(1);
+
+std::thread_local! {
+    /// When the worker thread was marked idle last time, and whether it is still idle.
+    static IDLE: Cell<(Option<Instant>, bool)> = const { Cell::new((None, false)) };
+}
+
+/// Enables background actions and runs
+/// [`process_background_actions`](TCMalloc::process_background_actions) on the blocking pool
+/// of the runtime.
+///
+/// Background actions are stopped on the runtime shutdown, so the blocking task finishes
+/// within the background process sleep interval and does not block the shutdown. The task
+/// finishes immediately if background actions are not needed on this platform.
+///
+/// Fails with [`io::ErrorKind::AlreadyExists`] if background actions are already running, e.g.
+/// by [`BackgroundActions`](crate::BackgroundActions) or another runtime.
+pub fn spawn_background_actions(handle: &Handle) -> io::Result<JoinHandle<()>> {
+    if !TCMalloc::needs_process_background_actions() {
+        return Ok(handle.spawn_blocking(|| {}));
+    }
+    let running = Running::acquire()?;
+    unsafe { BridgeSetBackgroundProcessActionsEnabled(true) };
+    let task = handle.spawn_blocking(move || running.run());
+    // Tasks are dropped on the runtime shutdown before it waits for the blocking pool. Only
+    // the blocking task runs background actions until it returns, so stopping them does not
+    // affect other owners.
+    handle.spawn(async {
+        let _stop = StopOnDrop;
+        future::pending::<()>().await;
+    });
+    Ok(task)
+}
+
+/// Marks the worker thread as idle, pass it to
+/// [`Builder::on_thread_park`](::tokio::runtime::Builder::on_thread_park).
+///
+/// Idle threads return their thread cache to the central free lists, which matters for the
+/// `deprecated_perthread` feature. Marking the thread idle destroys its thread cache, which is
+/// rebuilt when the thread allocates again, so it is expensive. Workers park very often, so
+/// the same thread is marked idle at most once a second and other parks are no-op.
+pub fn on_thread_park() {
+    let _ = IDLE.try_with(|idle| {
+        let now = Instant::now();
+        if idle
+            .get()
+            .0
+            .is_none_or(|last| now.duration_since(last) >= IDLE_INTERVAL)
+        {
+            TCMalloc::mark_thread_idle();
+            idle.set((Some(now), true));
+        }
+    });
+}
+
+/// Marks the worker thread as busy if [`on_thread_park`] has marked it idle, pass it to
+/// [`Builder::on_thread_unpark`](::tokio::runtime::Builder::on_thread_unpark).
+pub fn on_thread_unpark() {
+    let _ = IDLE.try_with(|idle| {
+        if let (last, true) = idle.get() {
+            TCMalloc::mark_thread_busy();
+            idle.set((last, false));
+        }
+    });
+}
+
+struct StopOnDrop;
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        background::stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::background::TEST_LOCK;
+    use ::tokio::runtime::Builder;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_thread_park() {
+        std::thread::spawn(|| {
+            on_thread_park();
+            let (Some(last), true) = IDLE.get() else {
+                panic!("thread is not marked idle");
+            };
+            on_thread_unpark();
+            assert_eq!(IDLE.get(), (Some(last), false));
+            // Parking again right away does not mark the thread idle.
+            on_thread_park();
+            assert_eq!(IDLE.get(), (Some(last), false));
+            on_thread_unpark();
+            assert_eq!(IDLE.get(), (Some(last), false));
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_spawn_background_actions() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(2)
+            .on_thread_park(on_thread_park)
+            .on_thread_unpark(on_thread_unpark)
+            .build()
+            .unwrap();
+        let task = spawn_background_actions(runtime.handle()).unwrap();
+        assert_eq!(
+            spawn_background_actions(runtime.handle())
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(
+            crate::BackgroundActions::start().unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        let sum = runtime.block_on(async {
+            let tasks: Vec<_> = (0..16)
+                .map(|i| ::tokio::spawn(async move { (0..i).collect::<Vec<usize>>().len() }))
+                .collect();
+            let mut sum = 0;
+            for task in tasks {
+                sum += task.await.unwrap();
+            }
+            sum
+        });
+        assert_eq!(sum, 120);
+        assert!(!task.is_finished());
+        // Returns after the background actions loop exits.
+        drop(runtime);
+
+        crate::BackgroundActions::start().unwrap().unwrap().stop();
+    }
+}